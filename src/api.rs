@@ -0,0 +1,569 @@
+use std::{
+	io::Read,
+	path::{Path, PathBuf},
+	sync::Arc,
+	thread,
+	time::{Duration, Instant}
+};
+
+use reqwest::{
+	header::{CONTENT_LENGTH, LOCATION},
+	redirect::Policy
+};
+
+pub use crate::{
+	compatibility::check_compatibility,
+	preflight::Preflight,
+	prerequisites::Prerequisite,
+	processes::{process_names, request_close, running_processes, ProcessWatch}
+};
+use crate::{
+	compatibility::{release_version_from_url, Compatibility},
+	conflicts::{scan_game_folder, Artifact},
+	detection::{Candidate, Detection, Source, SourceStatus},
+	error::InstallerError,
+	game_folder::{merge_folder, resolve_selection, GameFolder, GameFolderCheck, Problem},
+	game_version::read_game_version,
+	install::InstallOptions,
+	preflight::{uncompressed_size, PreflightProblem},
+	prerequisites::NativeProbe,
+	processes::{close_and_wait, RunningProcess, CLOSE_TIMEOUT},
+	progress::{CancellationToken, Progress},
+	scan::DriveScan,
+	uninstall::UninstallReport
+};
+
+// This is just so that rustfmt doesn't completely stop formatting the codebase
+// (it has an issue with print width that causes it to not format the whole function)
+pub static FRAMEWORK_DOWNLOAD_URL: &str =
+	"https://github.com/atampy25/simple-mod-framework/releases/latest/download/Release.zip";
+
+/// How often detection checks for results and cancellation.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How much of the download is read between progress events.
+const DOWNLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Game folders found so far, sorted by whether the framework can be installed to them.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct FoundGames {
+	pub installable: Vec<GameFolder>,
+
+	/// Folders the framework is already installed in; these are usually installable too.
+	pub already_installed: Vec<PathBuf>,
+
+	/// Folders that were reported but aren't usable game folders.
+	pub rejected: Vec<GameFolder>
+}
+
+impl FoundGames {
+	/// Sort folders from [`GameFolder::inspect`] and merge them into the folders found so far.
+	pub fn add(&mut self, folders: Vec<GameFolder>) {
+		for folder in folders {
+			if folder.check.framework_already_installed
+				&& !self.already_installed.contains(&folder.path)
+			{
+				self.already_installed.push(folder.path.to_owned());
+			}
+
+			if folder.check.is_valid() {
+				merge_folder(&mut self.installable, folder);
+			} else {
+				merge_folder(&mut self.rejected, folder);
+			}
+		}
+	}
+
+	pub fn clear(&mut self) {
+		self.installable.clear();
+		self.already_installed.clear();
+		self.rejected.clear();
+	}
+}
+
+/// How searching every drive is going.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DriveScanStatus {
+	/// Still searching, in `location` once the first one's been reached; `cancelled` if the
+	/// search has been asked to stop but hasn't yet.
+	Searching {
+		location: Option<PathBuf>,
+		cancelled: bool
+	},
+
+	Cancelled,
+	Finished
+}
+
+/// A search for game folders in the background, checking every store and, if asked, every drive.
+pub struct GameSearch {
+	detection: Detection,
+	drive_scan: Option<DriveScan>,
+	on_event: Arc<dyn Fn() + Send + Sync>
+}
+
+impl GameSearch {
+	/// Start checking every store; `on_event` is called from a search thread whenever there's
+	/// something new to [`poll`](Self::poll).
+	pub fn start(on_event: impl Fn() + Send + Sync + 'static) -> Self {
+		let on_event = Arc::new(on_event);
+
+		Self {
+			detection: Detection::start({
+				let on_event = on_event.clone();
+				move || on_event()
+			}),
+			drive_scan: None,
+			on_event
+		}
+	}
+
+	/// Search every drive too, which can take minutes.
+	pub fn scan_drives(&mut self) {
+		if self.drive_scan.is_none() {
+			let on_event = self.on_event.clone();
+			self.drive_scan = Some(DriveScan::start(move || on_event()));
+		}
+	}
+
+	/// Add a folder or file someone picked themselves, resolving it to the game folder it belongs
+	/// to. Gives what the selection was taken to mean, if it wasn't a game folder itself.
+	pub fn add_manual(&mut self, selected: &Path) -> Option<String> {
+		let resolution = resolve_selection(selected);
+
+		self.detection.add_manual(resolution.path);
+		resolution.explanation
+	}
+
+	/// Collect the folders found since the last poll.
+	pub fn poll(&mut self) -> Vec<GameFolder> {
+		let mut folders = self.detection.poll();
+
+		if let Some(scan) = &mut self.drive_scan {
+			folders.extend(scan.poll());
+		}
+
+		folders
+	}
+
+	/// How each store (and folder picked by hand) went.
+	pub fn statuses(&self) -> &[(Source, SourceStatus)] {
+		&self.detection.statuses
+	}
+
+	/// Whether every store has been checked; searching drives is tracked separately.
+	pub fn is_finished(&self) -> bool {
+		self.detection.is_finished()
+	}
+
+	/// How searching every drive is going, if it's been started.
+	pub fn drive_scan(&self) -> Option<DriveScanStatus> {
+		self.drive_scan.as_ref().map(|x| {
+			if !x.finished {
+				DriveScanStatus::Searching {
+					location: x.current_location.to_owned(),
+					cancelled: x.is_cancelled()
+				}
+			} else if x.is_cancelled() {
+				DriveScanStatus::Cancelled
+			} else {
+				DriveScanStatus::Finished
+			}
+		})
+	}
+
+	/// Stop waiting for stores that haven't finished and stop searching drives; whatever was found
+	/// so far is kept.
+	pub fn cancel(&mut self) {
+		self.detection.cancel();
+
+		if let Some(scan) = &self.drive_scan {
+			scan.cancel();
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct DetectOptions {
+	/// Also search every drive, which can take minutes.
+	pub drive_scan: bool,
+
+	/// Stop waiting for sources after this long and return what was found.
+	pub timeout: Option<Duration>
+}
+
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct DetectedGames {
+	pub games: FoundGames,
+
+	/// How each source went; sources still running when detection stopped are cancelled.
+	pub statuses: Vec<(Source, SourceStatus)>
+}
+
+/// Check every store (and optionally every drive) for the game, blocking until they've all
+/// finished.
+///
+/// Cancelling or timing out isn't an error; whatever was found by then is returned.
+pub fn detect_games(
+	options: &DetectOptions,
+	progress: &mut dyn FnMut(Progress),
+	cancel: &CancellationToken
+) -> DetectedGames {
+	let mut search = GameSearch::start(|| {});
+	let mut games = FoundGames::default();
+	let mut reported = vec![];
+
+	if options.drive_scan {
+		search.scan_drives();
+	}
+
+	let started = Instant::now();
+
+	loop {
+		let previous_scan = search.drive_scan();
+
+		games.add(search.poll());
+
+		for (source, status) in search.statuses() {
+			if let SourceStatus::Done(found) = status {
+				if !reported.contains(source) {
+					reported.push(*source);
+
+					progress(Progress::SourceFinished {
+						source: source.name(),
+						found: *found
+					});
+				}
+			}
+		}
+
+		let scan = search.drive_scan();
+
+		if let Some(DriveScanStatus::Searching {
+			location: Some(location),
+			..
+		}) = &scan
+		{
+			if scan != previous_scan {
+				progress(Progress::Searching(location.to_owned()));
+			}
+		}
+
+		if search.is_finished() && !matches!(scan, Some(DriveScanStatus::Searching { .. })) {
+			break;
+		}
+
+		if cancel.is_cancelled() || options.timeout.map_or(false, |x| started.elapsed() > x) {
+			search.cancel();
+			break;
+		}
+
+		thread::sleep(POLL_INTERVAL);
+	}
+
+	DetectedGames {
+		games,
+		statuses: search.statuses().to_owned()
+	}
+}
+
+/// Check a folder someone picked themselves, failing if the framework can't be installed to it.
+///
+/// Updating installs over an existing install, so `allow_installed` accepts folders the framework
+/// is already installed in.
+pub fn check_game_folder(path: &Path, allow_installed: bool) -> Result<GameFolder, InstallerError> {
+	let folder = GameFolder::inspect(Candidate {
+		path: path.to_owned(),
+		username: None,
+		source: Source::Manual
+	});
+
+	let problems = folder
+		.check
+		.problems()
+		.into_iter()
+		.filter(|x| !(allow_installed && *x == Problem::FrameworkAlreadyInstalled))
+		.collect::<Vec<_>>();
+
+	if problems.is_empty() {
+		Ok(folder)
+	} else {
+		Err(InstallerError::Verification {
+			path: path.to_owned(),
+			detail: problems
+				.iter()
+				.map(|x| x.explanation())
+				.collect::<Vec<_>>()
+				.join("\n")
+		})
+	}
+}
+
+/// The latest framework release, without downloading it.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Release {
+	pub version: Option<String>,
+
+	/// The size of the download, in bytes.
+	pub size: Option<u64>
+}
+
+pub fn latest_release() -> Result<Release, InstallerError> {
+	let asset = latest_release_asset()?;
+
+	let size = reqwest::blocking::Client::new()
+		.head(&asset)
+		.send()
+		.and_then(|x| x.error_for_status())
+		.map_err(|e| network_error(&asset, format!("{e:?}")))?
+		.headers()
+		.get(CONTENT_LENGTH)
+		.and_then(|x| x.to_str().ok())
+		.and_then(|x| x.parse().ok());
+
+	Ok(Release {
+		version: release_version_from_url(&asset),
+		size
+	})
+}
+
+/// The asset of the specific release the latest release download redirects to. The redirect isn't
+/// followed, as that goes on to a storage URL without the release in it.
+fn latest_release_asset() -> Result<String, InstallerError> {
+	let response = reqwest::blocking::Client::builder()
+		.redirect(Policy::none())
+		.build()
+		.and_then(|x| x.head(FRAMEWORK_DOWNLOAD_URL).send())
+		.map_err(|e| network_error(FRAMEWORK_DOWNLOAD_URL, format!("{e:?}")))?;
+
+	response
+		.headers()
+		.get(LOCATION)
+		.and_then(|x| x.to_str().ok())
+		.map(|x| x.to_owned())
+		.ok_or_else(|| {
+			network_error(
+				FRAMEWORK_DOWNLOAD_URL,
+				format!(
+					"The latest release didn't redirect to a release (status {})",
+					response.status()
+				)
+			)
+		})
+}
+
+/// A downloaded framework release.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Download {
+	pub data: Vec<u8>,
+	pub version: Option<String>
+}
+
+/// Download the latest framework release and check it's a framework release.
+pub fn download_framework(
+	progress: &mut dyn FnMut(Progress),
+	cancel: &CancellationToken
+) -> Result<Download, InstallerError> {
+	let _span = tracing::info_span!("download", url = FRAMEWORK_DOWNLOAD_URL).entered();
+
+	let asset = latest_release_asset()?;
+
+	tracing::info!("Downloading the framework from {asset}");
+
+	// The default timeout covers the whole download, which can take longer on slow connections
+	let mut response = reqwest::blocking::Client::builder()
+		.timeout(None)
+		.build()
+		.and_then(|x| x.get(&asset).send())
+		.and_then(|x| x.error_for_status())
+		.map_err(|e| network_error(&asset, format!("{e:?}")))?;
+
+	let total = response.content_length();
+
+	let mut data = Vec::with_capacity(total.unwrap_or_default() as usize);
+	let mut chunk = vec![0; DOWNLOAD_CHUNK_SIZE];
+
+	loop {
+		cancel.check()?;
+
+		let read = response
+			.read(&mut chunk)
+			.map_err(|e| network_error(&asset, format!("{e:?}")))?;
+
+		if read == 0 {
+			break;
+		}
+
+		data.extend_from_slice(&chunk[..read]);
+
+		progress(Progress::Downloading {
+			received: data.len() as u64,
+			total
+		});
+	}
+
+	tracing::info!("Downloaded {} bytes", data.len());
+
+	uncompressed_size(&data).map_err(|e| {
+		network_error(
+			&asset,
+			format!("The download isn't a framework release: {e:?}")
+		)
+	})?;
+
+	Ok(Download {
+		data,
+		version: release_version_from_url(&asset)
+	})
+}
+
+fn network_error(url: &str, detail: String) -> InstallerError {
+	tracing::error!("Download failed: {detail}");
+
+	InstallerError::Network {
+		url: url.to_owned(),
+		detail
+	}
+}
+
+/// What installing a download to a game folder would run into.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct InstallPlan {
+	pub game_folder: PathBuf,
+
+	/// The space the framework takes up once extracted, in bytes.
+	pub needed_space: u64,
+
+	pub problems: Vec<PreflightProblem>,
+
+	/// Programs running from the game folder, which have to be closed first.
+	pub running: Vec<RunningProcess>,
+
+	/// Files left behind by other mod tools.
+	pub artifacts: Vec<Artifact>,
+
+	pub compatibility: Compatibility,
+	pub already_installed: bool
+}
+
+impl InstallPlan {
+	/// The error installing would stop with right away, if any.
+	pub fn blocker(&self) -> Option<InstallerError> {
+		let problems = self
+			.problems
+			.iter()
+			.filter(|x| x.is_blocking())
+			.cloned()
+			.collect::<Vec<_>>();
+
+		if !problems.is_empty() {
+			Some(InstallerError::Preflight {
+				path: self.game_folder.to_owned(),
+				problems
+			})
+		} else if !self.running.is_empty() {
+			Some(InstallerError::InUse {
+				path: self.game_folder.to_owned(),
+				processes: process_names(&self.running)
+			})
+		} else {
+			None
+		}
+	}
+}
+
+/// Check a game folder for everything that could get in the way of installing a download to it,
+/// without changing anything.
+pub fn plan_install(
+	download: &Download,
+	game_folder: &Path
+) -> Result<InstallPlan, InstallerError> {
+	let needed_space =
+		uncompressed_size(&download.data).map_err(|e| InstallerError::Verification {
+			path: game_folder.to_owned(),
+			detail: format!("The download isn't a framework release: {e:?}")
+		})?;
+
+	Ok(InstallPlan {
+		game_folder: game_folder.to_owned(),
+		needed_space,
+		problems: Preflight::check(game_folder).problems_for(needed_space),
+		running: running_processes(game_folder),
+		artifacts: scan_game_folder(game_folder),
+		compatibility: check_compatibility(
+			download.version.as_deref(),
+			read_game_version(game_folder).ok()
+		),
+		already_installed: GameFolderCheck::new(game_folder).framework_already_installed
+	})
+}
+
+/// Install the framework from a download to a game folder.
+pub fn install(
+	data: &[u8],
+	game_folder: &Path,
+	options: &InstallOptions,
+	progress: &mut dyn FnMut(Progress),
+	cancel: &CancellationToken
+) -> Result<(), InstallerError> {
+	crate::install::install(data, game_folder, options, progress, cancel)
+}
+
+/// Install a newer framework over an existing install, keeping its config and game baseline.
+pub fn update(
+	data: &[u8],
+	game_folder: &Path,
+	options: &InstallOptions,
+	progress: &mut dyn FnMut(Progress),
+	cancel: &CancellationToken
+) -> Result<(), InstallerError> {
+	if !GameFolderCheck::new(game_folder).framework_already_installed {
+		return Err(InstallerError::NotInstalled {
+			path: game_folder.to_owned()
+		});
+	}
+
+	crate::install::install(data, game_folder, options, progress, cancel)
+}
+
+/// Remove the framework from a game folder and put the game back the way it was.
+pub fn uninstall(
+	game_folder: &Path,
+	progress: &mut dyn FnMut(Progress),
+	cancel: &CancellationToken
+) -> Result<UninstallReport, InstallerError> {
+	crate::uninstall::uninstall(game_folder, progress, cancel)
+}
+
+/// Close programs running from a game folder, waiting for them to exit.
+pub fn close_processes(
+	game_folder: &Path,
+	processes: &[RunningProcess]
+) -> Result<(), InstallerError> {
+	close_and_wait(processes, CLOSE_TIMEOUT).map_err(|_| InstallerError::InUse {
+		path: game_folder.to_owned(),
+		processes: process_names(processes)
+	})
+}
+
+/// The runtimes the Mod Manager needs that aren't installed.
+pub fn missing_prerequisites() -> Vec<&'static Prerequisite> {
+	// There's no registry to check outside Windows, and under Proton each game's prefix has its
+	// own runtimes
+	if cfg!(windows) {
+		crate::prerequisites::missing_prerequisites(&NativeProbe)
+	} else {
+		vec![]
+	}
+}
+
+/// Open the Mod Manager installed in a game folder.
+pub fn launch_mod_manager(game_folder: &Path) -> anyhow::Result<()> {
+	crate::launch::launch_mod_manager(&game_folder.join("Simple Mod Framework"))
+}
@@ -1,92 +1,423 @@
 use std::{
-	collections::HashMap,
-	fs,
-	io::Cursor,
-	os::windows::process::CommandExt,
+	mem,
 	path::{Path, PathBuf},
-	process::Command
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, Mutex
+	}
 };
 
 use anyhow::Context;
-use base64::{engine::general_purpose, Engine};
-use dirs::home_dir;
-use egui::{CentralPanel, Color32, ComboBox, Context as EguiContext, RichText};
+use egui::{
+	Button, CentralPanel, Color32, ComboBox, Context as EguiContext, RichText, ScrollArea,
+	TopBottomPanel
+};
 use human_bytes::human_bytes;
-use ini::Ini;
-use mslnk::ShellLink;
 use poll_promise::Promise;
-use registry::{Data, Hive, Security};
-use rfd::FileDialog;
-use serde::Deserialize;
-use serde_json::Value;
-use zip_extract::extract;
-
-// This is just so that rustfmt doesn't completely stop formatting the codebase
-// (it has an issue with print width that causes it to not format the whole function)
-static FRAMEWORK_DOWNLOAD_URL: &str =
-	"https://github.com/atampy25/simple-mod-framework/releases/latest/download/Release.zip";
+use rfd::{FileDialog, MessageButtons, MessageDialog};
+
+use crate::{
+	api::{
+		check_compatibility, close_processes, download_framework, install, latest_release,
+		launch_mod_manager, missing_prerequisites, plan_install, process_names, request_close,
+		running_processes, uninstall, Download, DriveScanStatus, FoundGames, GameSearch, Preflight,
+		Prerequisite, ProcessWatch, Release
+	},
+	compatibility::Compatibility,
+	conflicts::BACKUP_FOLDER,
+	crash::{mark_crash_reports_seen, new_crash_reports, CrashReporter},
+	detection::SourceStatus,
+	diagnostics::{Diagnostics, DIAGNOSTICS_FILE_NAME},
+	error::InstallerError,
+	game_folder::GameFolder,
+	install::InstallOptions,
+	logging::Log,
+	platform::Platform,
+	processes::RunningProcess,
+	progress::{CancellationToken, Progress, Step},
+	shortcuts::ShortcutLocation,
+	uninstall::UninstallReport,
+	wizard::{WizardEvent, WizardState}
+};
 
 pub struct App {
-	download_size: Option<f64>,
-	download_promise: Option<Promise<Result<Vec<u8>, String>>>,
-	installation_done: bool,
-	error: Option<String>,
-	performed_automatic_check: bool,
-	valid_game_folders: Vec<(PathBuf, Option<String>)>,
-	already_installed_folders: Vec<PathBuf>,
-	selected_game_folder: Option<usize>,
-	check_paths: Vec<(PathBuf, Option<String>)>,
-	manually_selected_folder: bool
-}
-
-#[derive(Deserialize)]
-struct SteamLibraryFolder {
-	path: String,
-	apps: HashMap<String, String>
-}
-
-#[derive(Deserialize)]
-struct SteamUser {
-	#[serde(alias = "PersonaName")]
-	#[serde(alias = "personaname")]
-	persona_name: String,
-
-	#[serde(alias = "MostRecent")]
-	#[serde(alias = "mostrecent")]
-	#[serde(default)]
-	most_recent: bool,
-
-	#[serde(alias = "Timestamp")]
-	#[serde(alias = "timestamp")]
-	#[serde(default)]
-	timestamp: u64
+	/// The latest release's version and size, fetched in the background on the first frame.
+	release: Option<Promise<Result<Release, InstallerError>>>,
+	accepted_unsupported_game_version: bool,
+	back_up_conflicts: bool,
+	start_menu_shortcut: bool,
+	desktop_shortcut: bool,
+	all_users_shortcuts: bool,
+	launch_after_install: bool,
+	launch_result: Option<Result<(), String>>,
+	process_watch: Option<ProcessWatch>,
+
+	/// Checking the selected folder, which writes a test file to it.
+	preflight_promise: Option<(PathBuf, Promise<Preflight>)>,
+	missing_prerequisites: Vec<&'static Prerequisite>,
+	prerequisite_promise: Option<(&'static Prerequisite, Promise<Result<(), String>>)>,
+	prerequisite_error: Option<String>,
+	uninstall_cancel: CancellationToken,
+
+	/// Bytes downloaded so far, updated from the download thread.
+	download_received: Arc<AtomicU64>,
+	download_cancel: CancellationToken,
+	install_cancel: CancellationToken,
+
+	/// What installing or uninstalling is doing, updated from its thread.
+	current_step: Arc<Mutex<Option<Step>>>,
+	state: WizardState,
+	error_report_copied: bool,
+	anonymize_diagnostics: bool,
+	diagnostics_result: Option<Result<PathBuf, String>>,
+	search: Option<GameSearch>,
+	games: FoundGames,
+	manually_selected_folder: bool,
+	manual_resolution: Option<String>,
+	log: Option<Log>,
+	crash_reporter: Option<CrashReporter>,
+	new_crash_reports: Vec<PathBuf>
 }
 
 impl App {
 	/// Called once before the first frame.
 	pub fn new() -> Self {
-		let download_size = (|| -> anyhow::Result<f64> {
-			Ok(reqwest::blocking::Client::new()
-				.head(FRAMEWORK_DOWNLOAD_URL)
-				.send()?
-				.headers()
-				.get("Content-Length")
-				.context("Content-Length")?
-				.to_str()?
-				.parse()?)
-		})();
-
 		App {
-			download_size: download_size.ok(),
-			download_promise: None,
-			installation_done: false,
-			error: None,
-			performed_automatic_check: false,
-			valid_game_folders: vec![],
-			already_installed_folders: vec![],
-			selected_game_folder: None,
-			check_paths: vec![],
-			manually_selected_folder: false
+			release: None,
+			accepted_unsupported_game_version: false,
+			back_up_conflicts: false,
+			start_menu_shortcut: true,
+			desktop_shortcut: false,
+			all_users_shortcuts: false,
+			launch_after_install: true,
+			launch_result: None,
+			process_watch: None,
+			preflight_promise: None,
+			missing_prerequisites: missing_prerequisites(),
+			prerequisite_promise: None,
+			prerequisite_error: None,
+			uninstall_cancel: CancellationToken::new(),
+			download_received: Arc::new(AtomicU64::new(0)),
+			download_cancel: CancellationToken::new(),
+			install_cancel: CancellationToken::new(),
+			current_step: Arc::new(Mutex::new(None)),
+			state: WizardState::default(),
+			error_report_copied: false,
+			anonymize_diagnostics: false,
+			diagnostics_result: None,
+			search: None,
+			games: FoundGames::default(),
+			manually_selected_folder: false,
+			manual_resolution: None,
+			log: None,
+			crash_reporter: None,
+			new_crash_reports: new_crash_reports()
+		}
+	}
+
+	/// Show the log in the log viewer.
+	pub fn with_log(mut self, log: Log) -> Self {
+		self.log = Some(log);
+		self
+	}
+
+	/// Keep crash reports up to date with what the installer is doing.
+	pub fn with_crash_reporter(mut self, crash_reporter: CrashReporter) -> Self {
+		self.crash_reporter = Some(crash_reporter);
+		self
+	}
+
+	/// The latest release, once it's been fetched.
+	fn release(&self) -> Option<&Release> {
+		self.release
+			.as_ref()
+			.and_then(|x| x.ready())
+			.and_then(|x| x.as_ref().ok())
+	}
+
+	fn add_folders(&mut self, folders: Vec<GameFolder>) {
+		self.games.add(folders);
+		self.select_default_folder();
+	}
+
+	/// Select the first game folder found if none has been selected yet.
+	fn select_default_folder(&mut self) {
+		if let WizardState::Choosing { selected, .. } = &mut self.state {
+			if selected.is_none() && !self.games.installable.is_empty() {
+				*selected = Some(0);
+			}
+		}
+	}
+
+	/// Add a folder or file the user picked (or dropped onto the window) as a candidate, resolving
+	/// it to the game folder it belongs to.
+	fn select_manually(&mut self, selected: PathBuf) {
+		if let Some(search) = &mut self.search {
+			self.manually_selected_folder = true;
+			self.manual_resolution = search.add_manual(&selected);
+		}
+	}
+
+	fn start_download(
+		&mut self,
+		ctx: &EguiContext
+	) -> Promise<Result<Arc<Download>, InstallerError>> {
+		let ctx = ctx.clone();
+		let received = self.download_received.clone();
+
+		self.download_cancel = CancellationToken::new();
+		let cancel = self.download_cancel.to_owned();
+
+		received.store(0, Ordering::Relaxed);
+
+		Promise::spawn_thread("download", move || {
+			let result = download_framework(
+				&mut |progress| {
+					if let Progress::Downloading { received: x, .. } = progress {
+						received.store(x, Ordering::Relaxed);
+						ctx.request_repaint();
+					}
+				},
+				&cancel
+			);
+
+			ctx.request_repaint();
+			result.map(Arc::new)
+		})
+	}
+
+	/// Install the download to a game folder in the background.
+	fn start_install(
+		&mut self,
+		ctx: &EguiContext,
+		folder: usize,
+		download: &Arc<Download>
+	) -> Result<Promise<Result<(), InstallerError>>, InstallerError> {
+		let folder = self
+			.games
+			.installable
+			.get(folder)
+			.context("game folder index")
+			.map_err(InstallerError::internal)?;
+
+		let options = self.install_options(folder, download);
+		let game_folder = folder.path.to_owned();
+		let download = download.to_owned();
+
+		let ctx = ctx.clone();
+		let current_step = self.current_step.clone();
+
+		self.install_cancel = CancellationToken::new();
+		let cancel = self.install_cancel.to_owned();
+
+		*current_step.lock().unwrap() = None;
+
+		Ok(Promise::spawn_thread("install", move || {
+			let result = install(
+				&download.data,
+				&game_folder,
+				&options,
+				&mut |progress| {
+					if let Progress::Step(step) = progress {
+						*current_step.lock().unwrap() = Some(step);
+						ctx.request_repaint();
+					}
+				},
+				&cancel
+			);
+
+			ctx.request_repaint();
+			result
+		}))
+	}
+
+	/// What installing or uninstalling is doing, for showing next to a spinner.
+	fn step_description(&self, fallback: &str) -> String {
+		match *self.current_step.lock().unwrap() {
+			Some(step) => format!("{}...", step.description()),
+			None => fallback.to_owned()
+		}
+	}
+
+	/// Close the processes, then uninstall the framework from a folder in the background.
+	fn start_uninstall(
+		&mut self,
+		ctx: &EguiContext,
+		folder: &Path,
+		close: &[RunningProcess]
+	) -> Promise<Result<UninstallReport, InstallerError>> {
+		let ctx = ctx.clone();
+		let folder = folder.to_owned();
+		let close = close.to_owned();
+		let current_step = self.current_step.clone();
+
+		self.uninstall_cancel = CancellationToken::new();
+		let cancel = self.uninstall_cancel.to_owned();
+
+		*current_step.lock().unwrap() = None;
+
+		Promise::spawn_thread("uninstall", move || {
+			let result = close_processes(&folder, &close).and_then(|_| {
+				uninstall(
+					&folder,
+					&mut |progress| {
+						if let Progress::Step(step) = progress {
+							*current_step.lock().unwrap() = Some(step);
+							ctx.request_repaint();
+						}
+					},
+					&cancel
+				)
+			});
+			ctx.request_repaint();
+			result
+		})
+	}
+
+	/// Start the work of a state that's just been entered (or retried), keeping it in the state.
+	fn start_pending_work(&mut self, ctx: &EguiContext) {
+		self.state = match mem::take(&mut self.state) {
+			WizardState::Downloading {
+				folder,
+				download: None
+			} => WizardState::Downloading {
+				folder,
+				download: Some(self.start_download(ctx))
+			},
+
+			WizardState::Installing {
+				folder,
+				download,
+				install: None
+			} => match self.start_install(ctx, folder, &download) {
+				Ok(install) => WizardState::Installing {
+					folder,
+					download,
+					install: Some(install)
+				},
+
+				Err(e) => {
+					self.state = WizardState::Installing {
+						folder,
+						download,
+						install: None
+					};
+
+					self.transition(WizardEvent::Failed(e));
+					return;
+				}
+			},
+
+			WizardState::Uninstalling {
+				folder,
+				close,
+				uninstall: None
+			} => WizardState::Uninstalling {
+				uninstall: Some(self.start_uninstall(ctx, &folder, &close)),
+				folder,
+				close
+			},
+
+			state => state
+		};
+	}
+
+	fn transition(&mut self, event: WizardEvent) {
+		let state = mem::take(&mut self.state).next(event);
+
+		if state.name() != self.state.name() {
+			tracing::info!("Now {}", state.name());
+		}
+
+		self.state = state;
+	}
+
+	/// Run the step that failed again; its work starts again on entering it.
+	fn retry(&mut self) {
+		self.error_report_copied = false;
+		self.transition(WizardEvent::Retry);
+	}
+
+	/// Leave the error and go back to choosing a game folder.
+	fn back(&mut self) {
+		self.error_report_copied = false;
+		self.download_cancel.cancel();
+		self.install_cancel.cancel();
+		self.uninstall_cancel.cancel();
+		self.transition(WizardEvent::Back);
+		self.launch_result = None;
+		self.accepted_unsupported_game_version = false;
+		self.rescan();
+	}
+
+	/// Ask where to save a diagnostics bundle and write it there.
+	fn export_diagnostics(&mut self) {
+		if let Some(path) = FileDialog::new()
+			.set_file_name(DIAGNOSTICS_FILE_NAME)
+			.add_filter("Zip file", &["zip"])
+			.save_file()
+		{
+			self.diagnostics_result = Some(
+				Diagnostics {
+					statuses: self
+						.search
+						.as_ref()
+						.map(|x| x.statuses())
+						.unwrap_or_default(),
+					game_folders: &self.games.installable,
+					already_installed: &self.games.already_installed,
+					rejected_folders: &self.games.rejected,
+					last_error: self.state.last_error(),
+					anonymize: self.anonymize_diagnostics
+				}
+				.export(&path)
+				.map(|_| path)
+				.map_err(|x| format!("{x:#}"))
+			);
+		}
+	}
+
+	/// The game folder being installed to, or the one selected to install to.
+	fn selected_folder(&self) -> Option<&GameFolder> {
+		self.state
+			.folder()
+			.and_then(|x| self.games.installable.get(x))
+	}
+
+	fn install_options(&self, folder: &GameFolder, download: &Download) -> InstallOptions {
+		InstallOptions {
+			back_up_conflicts: self.back_up_conflicts,
+			start_menu_shortcut: self.start_menu_shortcut,
+			desktop_shortcut: self.desktop_shortcut,
+			all_users_shortcuts: self.all_users_shortcuts,
+			..InstallOptions::for_game_folder(
+				folder,
+				download
+					.version
+					.to_owned()
+					.or_else(|| self.release().and_then(|x| x.version.to_owned()))
+			)
+		}
+	}
+
+	/// Open the Mod Manager in the selected game folder.
+	fn launch_mod_manager(&mut self) {
+		if let Some(folder) = self.selected_folder() {
+			self.launch_result =
+				Some(launch_mod_manager(&folder.path).map_err(|x| format!("{x:#}")));
+		}
+	}
+
+	/// Forget everything found so far and run detection again from scratch, unless the folder
+	/// can't be changed right now.
+	fn rescan(&mut self) {
+		self.transition(WizardEvent::Rescan);
+
+		if matches!(self.state, WizardState::Detecting { .. }) {
+			self.search = None;
+			self.games.clear();
+			self.manually_selected_folder = false;
+			self.manual_resolution = None;
 		}
 	}
 }
@@ -101,449 +432,636 @@ impl eframe::App for App {
 	fn update(&mut self, ctx: &EguiContext, _frame: &mut eframe::Frame) {
 		ctx.set_pixels_per_point(3.0);
 
-		if let Some(error) = &self.error {
+		self.release.get_or_insert_with(|| {
+			let ctx = ctx.clone();
+
+			Promise::spawn_thread("release", move || {
+				let result = latest_release();
+
+				if let Err(e) = &result {
+					tracing::warn!("Couldn't get the latest release: {e}\n{}", e.detail());
+				}
+
+				ctx.request_repaint();
+				result
+			})
+		});
+
+		let dropped_path = ctx
+			.input()
+			.raw
+			.dropped_files
+			.iter()
+			.find_map(|x| x.path.to_owned());
+
+		if let Some(path) = dropped_path {
+			if self.state.is_choosing() {
+				self.select_manually(path);
+			}
+		}
+
+		if let Some(crash_reporter) = &self.crash_reporter {
+			crash_reporter.set_selected_folder(self.selected_folder().map(|x| x.path.as_path()));
+		}
+
+		TopBottomPanel::bottom("log").show(ctx, |ui| {
+			if let Some(log) = &self.log {
+				ui.collapsing(RichText::from("Log").size(6.0), |ui| {
+					ScrollArea::vertical()
+						.max_height(120.0)
+						.stick_to_bottom(true)
+						.show(ui, |ui| {
+							for line in log.recent_lines() {
+								ui.label(RichText::from(line).monospace().size(6.0));
+							}
+						});
+
+					if let Some(path) = log.path() {
+						ui.label(RichText::from(format!("Saved to {}", path.display())).size(6.0));
+					}
+				});
+			}
+
+			ui.horizontal_wrapped(|ui| {
+				if ui
+					.button(RichText::from("Export diagnostics").size(6.0))
+					.clicked()
+				{
+					self.export_diagnostics();
+				}
+
+				ui.checkbox(
+					&mut self.anonymize_diagnostics,
+					RichText::from("Hide account names and user folders").size(6.0)
+				);
+
+				match &self.diagnostics_result {
+					Some(Ok(path)) => {
+						ui.label(
+							RichText::from(format!("✅ Saved to {}", path.display())).size(6.0)
+						);
+					}
+
+					Some(Err(e)) => {
+						ui.label(
+							RichText::from(format!("❌ Couldn't export diagnostics: {e}"))
+								.color(Color32::from_rgb(200, 50, 50))
+								.size(6.0)
+						);
+					}
+
+					None => {}
+				}
+			});
+		});
+
+		if let Some(error) = self.state.error().cloned() {
 			CentralPanel::default().show(ctx, |ui| {
 				ui.label(RichText::from("Welcome to the Simple Mod Framework!").strong());
 
 				ui.label(
-					"It seems a critical error has occurred. Try restarting the installation \
-					 process, or give Atampy26 the following error message on Hitman Forum (note \
-					 that this does not say Nexus Mods)."
+					RichText::from(format!("❌ {}", error.title()))
+						.color(Color32::from_rgb(200, 50, 50))
+						.size(8.0)
 				);
 
+				ui.label(RichText::from(error.explanation()).size(7.0));
+				ui.label(RichText::from(error.suggestion()).size(7.0));
+
+				ui.add_space(4.0);
+
+				ui.horizontal_wrapped(|ui| {
+					if ui.button(RichText::from("Retry").size(7.0)).clicked() {
+						self.retry();
+					}
+
+					if ui.button(RichText::from("Back").size(7.0)).clicked() {
+						self.back();
+					}
+
+					if ui.button(RichText::from("Copy report").size(7.0)).clicked() {
+						ui.output().copied_text = error.report();
+						self.error_report_copied = true;
+					}
+
+					if self.error_report_copied {
+						ui.label(RichText::from("✅ Copied").size(6.0));
+					}
+				});
+
+				ui.add_space(4.0);
+
 				ui.label(
-					RichText::from(error)
-						.color(Color32::from_rgb(200, 50, 50))
-						.size(7.0)
+					RichText::from(
+						"If you can't fix it, paste the report into a post for Atampy26 on Hitman \
+						 Forum (note that this does not say Nexus Mods)."
+					)
+					.size(6.0)
 				);
+
+				ui.collapsing(RichText::from("Technical details").size(6.0), |ui| {
+					ui.label(RichText::from(error.detail()).size(6.0));
+				});
 			});
 		} else {
 			CentralPanel::default().show(ctx, |ui| {
 				ui.label(RichText::from("Welcome to the Simple Mod Framework!").strong());
 
-				ui.label("Let's find your game folder.");
+				if !self.new_crash_reports.is_empty() {
+					ui.label(
+						RichText::from(format!(
+							"⚠ The installer crashed last time. The crash report was saved to {}; \
+							 please include it when asking for help on Hitman Forum (not Nexus \
+							 Mods). Exporting diagnostics includes it.",
+							self.new_crash_reports
+								.iter()
+								.map(|x| x.display().to_string())
+								.collect::<Vec<_>>()
+								.join(", ")
+						))
+						.color(Color32::from_rgb(220, 150, 30))
+						.size(7.0)
+					);
+
+					ui.horizontal_wrapped(|ui| {
+						if ui
+							.button(RichText::from("Export diagnostics").size(7.0))
+							.clicked()
+						{
+							self.export_diagnostics();
+						}
 
-				ui.label(
-					RichText::from(
-						"The framework will only work if you're using a copy of the game bought \
-						 from Steam, Epic Games or Microsoft."
-					)
-					.size(8.0)
-				);
+						if ui.button(RichText::from("Dismiss").size(7.0)).clicked() {
+							mark_crash_reports_seen(&self.new_crash_reports);
+							self.new_crash_reports.clear();
+						}
+					});
 
-				ui.label(
-					RichText::from(
-						"If you're using a Microsoft version of the game (Xbox/Game Pass), you \
-						 may need to enable the \"Advanced management features\" option to be \
-						 able to install the framework. When that's done, you should be able to \
-						 select the game folder here."
-					)
-					.size(8.0)
-				);
+					ui.add_space(2.5);
+				}
 
-				ui.add_space(2.5);
-
-				let x = (|| -> anyhow::Result<()> {
-					if !self.performed_automatic_check {
-						let legendary_installed_path =
-							Path::new(&std::env::var("USERPROFILE").context("%USERPROFILE%")?)
-								.join(".config")
-								.join("legendary")
-								.join("installed.json");
-
-						// Check for a Legendary install
-						if legendary_installed_path.exists() {
-							let legendary_installed_data: Value = serde_json::from_slice(
-								&fs::read(legendary_installed_path)
-									.context("Reading legendary installed")?
-							)
-							.context("Legendary installed as JSON")?;
-
-							if let Some(data) = legendary_installed_data.get("Eider") {
-								self.check_paths.push((
-									PathBuf::from(
-										data.get("install_path")
-											.context("install_path")?
-											.as_str()
-											.context("as_str")?
-									),
-									Some(
-										serde_json::from_slice::<Value>(
-											&fs::read(
-												Path::new(&std::env::var("USERPROFILE")?)
-													.join(".config")
-													.join("legendary")
-													.join("user.json")
-											)
-											.context("Reading legendary user")?
+				if self.state.is_choosing() || self.state.is_uninstalling() {
+					ui.label("Let's find your game folder.");
+
+					ui.label(
+						RichText::from(
+							"The framework will only work if you're using a copy of the game \
+							 bought from Steam, Epic Games or Microsoft."
+						)
+						.size(8.0)
+					);
+
+					ui.label(
+						RichText::from(
+							"If you're using a Microsoft version of the game (Xbox/Game Pass), \
+							 you may need to enable the \"Advanced management features\" option \
+							 to be able to install the framework. When that's done, you should be \
+							 able to select the game folder here."
+						)
+						.size(8.0)
+					);
+
+					ui.add_space(2.5);
+
+					let x = (|| -> anyhow::Result<()> {
+						let search = self.search.get_or_insert_with(|| {
+							let ctx = ctx.clone();
+							GameSearch::start(move || ctx.request_repaint())
+						});
+
+						let folders = search.poll();
+						self.add_folders(folders);
+
+						if !self.games.installable.is_empty()
+							|| self.search.as_ref().map_or(false, |x| x.is_finished())
+						{
+							self.transition(WizardEvent::Detected);
+							self.select_default_folder();
+						}
+
+						if !self.games.installable.is_empty() {
+							if self.games.installable.len() == 1 {
+								ui.label(
+									RichText::from(if self.manually_selected_folder {
+										"✅ Game folder manually selected"
+									} else {
+										"✅ Game folder found automatically"
+									})
+									.size(7.0)
+								);
+							} else {
+								ComboBox::from_label(
+									RichText::from("Select your game folder").size(7.0)
+								)
+								.selected_text(
+									RichText::from(if let Some(x) = self.state.folder() {
+										String::from(
+											self.games
+												.installable
+												.get(x)
+												.context("selected game folder")?
+												.path
+												.to_string_lossy()
 										)
-										.context("Legendary user as JSON")?
-										.get("displayName")
-										.context("displayName")?
-										.as_str()
-										.context("as_str")?
-										.to_owned()
-									)
-								));
+									} else {
+										"".to_owned()
+									})
+									.size(7.0)
+								)
+								.width(200.0)
+								.show_ui(ui, |ui| {
+									let selected = match &mut self.state {
+										WizardState::Choosing { selected, .. } => selected,
+										_ => return
+									};
+
+									for (ind, folder) in self.games.installable.iter().enumerate() {
+										ui.selectable_value(
+											selected,
+											Some(ind),
+											RichText::from(format!(
+												"{} ({}{})",
+												folder.path.display(),
+												folder.source_names(),
+												folder
+													.game_version
+													.map(|x| format!(", game version {x}"))
+													.unwrap_or_default()
+											))
+											.size(7.0)
+										);
+									}
+								});
 							}
-						}
+						} else if !self.games.already_installed.is_empty() {
+							ui.label(
+								RichText::from(
+									"The framework is already installed! If you're trying to \
+									 update the framework, use the Mod Manager's integrated \
+									 update functionality, which you can find by opening the Mod \
+									 Manager and looking at the home page."
+								)
+								.size(7.0)
+							);
 
-						// Check for EOS manifests
-						if let Ok(hive) =
-							Hive::CurrentUser.open(r#"Software\Epic Games\EOS"#, Security::Read)
-						{
-							match hive.value("ModSdkMetadataDir") {
-								Ok(Data::String(d)) => {
-									if let Ok(entries) = fs::read_dir(d.to_string_lossy()) {
-										for entry in entries.filter_map(|x| x.ok()).filter(|x| {
-											x.file_type().ok().map(|x| x.is_file()).unwrap_or(false)
-										}) {
-											if let Ok(manifest_data) = serde_json::from_slice::<Value>(
-												&fs::read(entry.path()).with_context(|| {
-													format!(
-														"Reading EOS manifest {}",
-														entry.path().display()
-													)
-												})?
-											) {
-												if manifest_data
-													.get("AppName")
-													.context("AppName")?
-													.as_str()
-													.context("as_str")? == "Eider"
-												{
-													let mut username = None;
-
-													if Path::new(&std::env::var("LOCALAPPDATA")?)
-														.join("EpicGamesLauncher")
-														.join("Saved")
-														.join("Config")
-														.join("Windows")
-														.join("GameUserSettings.ini")
-														.exists()
-													{
-														if let Some(x) = Ini::load_from_file(
-															Path::new(&std::env::var(
-																"LOCALAPPDATA"
-															)?)
-															.join("EpicGamesLauncher")
-															.join("Saved")
-															.join("Config")
-															.join("Windows")
-															.join("GameUserSettings.ini")
-														)
-														.context("Reading GameUserSettings.ini")?
-														.section(Some("Offline"))
-														.and_then(|x| x.get("Data"))
-														{
-															if let Ok(x) =
-																general_purpose::STANDARD.decode(x)
-															{
-																if let Ok(x) =
-																	serde_json::from_slice::<Value>(
-																		&x
-																	) {
-																	if let Some(x) = x.get(0) {
-																		username = Some(
-																			x.get("DisplayName")
-																				.context(
-																					"DisplayName"
-																				)?
-																				.as_str()
-																				.context("as_str")?
-																				.to_owned()
-																		);
-																	}
-																}
-															}
-														};
-													}
+							ui.add_space(2.5);
 
-													self.check_paths.push((
-														PathBuf::from(
-															manifest_data
-																.get("InstallLocation")
-																.context("InstallLocation")?
-																.as_str()
-																.context("as_str")?
-														),
-														username
-													));
-												}
-											}
+							let mut event = None;
+							let mut done = false;
+
+							match &self.state {
+								WizardState::Uninstalling { uninstall, .. } => {
+									let step = self.step_description("Uninstalling...");
+
+									ui.horizontal_wrapped(|ui| {
+										ui.spinner();
+										ui.label(RichText::from(step).size(7.0));
+
+										if ui.button(RichText::from("Cancel").size(7.0)).clicked() {
+											self.uninstall_cancel.cancel();
 										}
-									}
+									});
+
+									event = match uninstall.as_ref().and_then(|x| x.ready()) {
+										None => None,
+										Some(Ok(report)) => {
+											Some(WizardEvent::Uninstalled(report.to_owned()))
+										}
+										Some(Err(InstallerError::Cancelled)) => {
+											Some(WizardEvent::Cancel)
+										}
+										Some(Err(e)) => {
+											tracing::error!(
+												"Uninstall failed: {e}\n{}",
+												e.detail()
+											);
+											Some(WizardEvent::Failed(e.to_owned()))
+										}
+									};
 								}
 
-								Ok(_) => Err(anyhow::anyhow!("Registry key ModSdkMetadataDir \
-								                              was not string"
-									.to_owned()))?,
+								WizardState::Uninstalled { report, .. } => {
+									ui.label(
+										RichText::from(format!(
+											"✅ Uninstalled; removed {} and restored {} files.",
+											report.removed.len(),
+											report.restored.len()
+										))
+										.size(7.0)
+									);
 
-								Err(_) => {}
-							}
-						}
+									for problem in &report.problems {
+										ui.label(
+											RichText::from(format!("⚠ {problem}"))
+												.color(Color32::from_rgb(220, 150, 30))
+												.size(6.0)
+										);
+									}
 
-						// Check for a Steam install
-						if let Ok(hive) =
-							Hive::CurrentUser.open(r#"Software\Valve\Steam"#, Security::Read)
-						{
-							match hive.value("SteamPath") {
-								Ok(Data::String(d)) => {
-									if let Ok(s) = fs::read_to_string(
-										if Path::new(&d.to_string_lossy())
-											.join("config")
-											.join("libraryfolders.vdf")
-											.exists()
+									done = ui.button(RichText::from("Done").size(7.0)).clicked();
+								}
+
+								_ => {
+									for folder in &self.games.already_installed {
+										if !(ui
+											.button(
+												RichText::from(format!(
+													"Uninstall from {}",
+													folder.display()
+												))
+												.size(7.0)
+											)
+											.clicked() && MessageDialog::new()
+											.set_title("Uninstall the framework")
+											.set_description(&format!(
+												"This will remove the framework and any mods it \
+												 deployed from {}, and put the game back the way \
+												 it was. Continue?",
+												folder.display()
+											))
+											.set_buttons(MessageButtons::YesNo)
+											.show())
 										{
-											Path::new(&d.to_string_lossy())
-												.join("config")
-												.join("libraryfolders.vdf")
-										} else {
-											Path::new(&d.to_string_lossy())
-												.join("steamapps")
-												.join("libraryfolders.vdf")
+											continue;
 										}
-									) {
-										let folders: HashMap<String, SteamLibraryFolder> =
-											keyvalues_serde::from_str(&s).context("VDF parse")?;
 
-										for folder in folders.values() {
-											if folder.apps.contains_key("1659040")
-												|| folder.apps.contains_key("1847520")
-											{
-												let users: HashMap<String, SteamUser> =
-													keyvalues_serde::from_str(
-														&fs::read_to_string(
-															Path::new(&d.to_string_lossy())
-																.join("config")
-																.join("loginusers.vdf")
-														)
-														.context("Reading loginusers.vdf")?
-													)?;
-
-												self.check_paths.push((
-													Path::new(&folder.path)
-														.join("steamapps")
-														.join("common")
-														.join("HITMAN 3"),
-													Some(
-														users
-															.values()
-															.find(|x| x.most_recent)
-															.unwrap_or(
-																users
-																	.values()
-																	.reduce(|cur, x| {
-																		if cur.timestamp
-																			< x.timestamp
-																		{
-																			x
-																		} else {
-																			cur
-																		}
-																	})
-																	.context(
-																		"Steam users was empty"
-																	)?
-															)
-															.persona_name
-															.to_owned()
-													)
-												));
-											}
+										let running = running_processes(folder);
+
+										if running.is_empty()
+											|| MessageDialog::new()
+												.set_title("Uninstall the framework")
+												.set_description(&format!(
+													"{} is running from the game folder and has \
+													 to be closed first. Close it and continue?",
+													process_names(&running)
+												))
+												.set_buttons(MessageButtons::YesNo)
+												.show()
+										{
+											event = Some(WizardEvent::Uninstall {
+												folder: folder.to_owned(),
+												close: running
+											});
 										}
-									};
+									}
 								}
+							}
 
-								Ok(_) => {
-									self.error =
-										Some("Registry key SteamPath was not string".to_owned());
+							// Whatever was removed before cancelling stays removed, so the folders
+							// are looked at again
+							if let Some(event) = event {
+								let cancelled = matches!(event, WizardEvent::Cancel);
+
+								self.transition(event);
+								ctx.request_repaint();
+
+								if cancelled {
+									self.rescan();
 								}
+							}
 
-								Err(_) => {}
+							if done {
+								self.rescan();
 							}
-						}
+						} else if !self.search.as_ref().map_or(true, |x| x.is_finished()) {
+							ui.horizontal_wrapped(|ui| {
+								ui.spinner();
+								ui.label(
+									RichText::from("Looking for your game folder...").size(7.0)
+								);
+							});
+						} else {
+							ui.label(
+								RichText::from(
+									"We couldn't find HITMAN 3 anywhere. Make sure you're trying \
+									 to install the framework on a copy of HITMAN 3 installed via \
+									 Steam, Epic Games Launcher/Legendary or the Xbox app, then \
+									 select your folder manually below (or drag it onto this \
+									 window), or let us search your drives for it."
+								)
+								.size(7.0)
+							);
 
-						// Check for a Microsoft install
-						if let Ok(proc_out) = Command::new("powershell")
-							.args([
-								"-Command",
-								"Get-AppxPackage -Name IOInteractiveAS.PC-HITMAN3-BaseGame"
-							])
-							.creation_flags(0x08000000) // CREATE_NO_WINDOW
-							.output()
-						{
-							if let Some(line) = String::from_utf8_lossy(&proc_out.stdout)
-								.lines()
-								.find(|x| x.starts_with("InstallLocation"))
-							{
-								let mut username = None;
+							ui.add_space(5.0);
 
-								if let Ok(hive) = Hive::CurrentUser
-									.open(r#"Software\Microsoft\XboxLive"#, Security::Read)
+							ui.horizontal_wrapped(|ui| {
+								if ui
+									.button(RichText::from("Select your game folder").size(7.0))
+									.clicked()
 								{
-									if let Ok(Data::String(d)) = hive.value("ModernGamertag") {
-										username = Some(d.to_string_lossy());
+									if let Some(folder) = FileDialog::new()
+										.set_title(
+											"Select your game folder; it should contain a folder \
+											 called Retail"
+										)
+										.pick_folder()
+									{
+										self.select_manually(folder);
 									}
 								}
 
-								self.check_paths.push((
-									fs::read_link(
-										line.split(':')
-											.skip(1)
-											.collect::<Vec<_>>()
-											.join(":")
-											.trim()
-									)?,
-									username
-								));
-							}
-						}
+								if ui
+									.button(RichText::from("Select HITMAN3.exe").size(7.0))
+									.clicked()
+								{
+									if let Some(file) = FileDialog::new()
+										.set_title("Select HITMAN3.exe; it's in the Retail folder")
+										.add_filter("HITMAN3.exe", &["exe"])
+										.pick_file()
+									{
+										self.select_manually(file);
+									}
+								}
 
-						for (path, username) in &self.check_paths {
-							// Game folder has Retail
-							let subfolder_retail = path.join("Retail").is_dir();
+								if let Some(search) = &mut self.search {
+									if search.drive_scan().is_none()
+										&& ui
+											.button(RichText::from("Search my drives").size(7.0))
+											.clicked()
+									{
+										search.scan_drives();
+									}
+								}
+							});
+						}
 
-							// Game folder has Runtime or Retail/Runtime
-							let subfolder_runtime = path.join("Runtime").is_dir()
-								|| path.join("Retail").join("Runtime").is_dir();
+						if let Some(search) = &self.search {
+							ui.add_space(2.5);
 
-							// User is not trying to install the framework on the wrong game
-							let ishitman3 = path.join("Retail").join("HITMAN3.exe").is_file();
+							for (source, status) in search.statuses() {
+								ui.label(
+									RichText::from(match status {
+										SourceStatus::Running => {
+											format!("⏳ {}: searching", source.name())
+										}
+										SourceStatus::Done(0) => {
+											format!("➖ {}: nothing found", source.name())
+										}
+										SourceStatus::Done(1) => {
+											format!("✅ {}: found 1 folder", source.name())
+										}
+										SourceStatus::Done(x) => {
+											format!("✅ {}: found {} folders", source.name(), x)
+										}
+										SourceStatus::Failed(e) => {
+											format!("❌ {}", e.explanation())
+										}
+										SourceStatus::Cancelled => {
+											format!("➖ {}: cancelled", source.name())
+										}
+									})
+									.size(6.0)
+								);
+							}
 
-							let framework_already_installed =
-								path.join("Simple Mod Framework").is_dir();
+							if let Some(explanation) = &self.manual_resolution {
+								ui.label(RichText::from(format!("ℹ {explanation}")).size(6.0));
+							}
 
-							if !framework_already_installed
-								&& subfolder_retail && subfolder_runtime
-								&& ishitman3 && !self
-								.valid_game_folders
-								.iter()
-								.any(|(x, y)| x == path && y.is_some())
-							{
-								self.valid_game_folders
-									.push((path.to_owned(), username.to_owned()));
-
-								self.valid_game_folders = self
-									.valid_game_folders
-									.iter()
-									.cloned()
-									.filter(|(x, y)| {
-										y.is_some()
-											|| !self
-												.valid_game_folders
-												.iter()
-												.any(|(a, b)| *a == *x && b.is_some())
-									})
-									.collect();
+							if !self.games.rejected.is_empty() {
+								ui.collapsing(
+									RichText::from(format!(
+										"Folders that weren't accepted ({})",
+										self.games.rejected.len()
+									))
+									.size(7.0),
+									|ui| {
+										for folder in &self.games.rejected {
+											ui.label(
+												RichText::from(format!(
+													"{} ({}{})",
+													folder.path.display(),
+													folder.source_names(),
+													folder
+														.game_version
+														.map(|x| format!(", game version {x}"))
+														.unwrap_or_default()
+												))
+												.size(6.0)
+											);
+
+											for problem in folder.check.problems() {
+												ui.label(
+													RichText::from(format!(
+														"    ❌ {}",
+														problem.explanation()
+													))
+													.size(6.0)
+												);
+											}
+										}
+									}
+								);
 							}
 
-							if framework_already_installed {
-								self.already_installed_folders.push(path.to_owned());
+							if search.is_finished() {
+								if ui.button(RichText::from("Rescan").size(7.0)).clicked() {
+									self.rescan();
+								}
+							} else if ui.button(RichText::from("Cancel").size(7.0)).clicked() {
+								if let Some(search) = &mut self.search {
+									search.cancel();
+								}
 							}
 						}
 
-						if !self.valid_game_folders.is_empty() {
-							self.selected_game_folder = Some(0);
-						}
+						if let Some(status) = self.search.as_ref().and_then(|x| x.drive_scan()) {
+							ui.add_space(2.5);
 
-						self.performed_automatic_check = true;
-					}
+							match status {
+								DriveScanStatus::Searching {
+									location,
+									cancelled
+								} => {
+									ui.horizontal_wrapped(|ui| {
+										ui.spinner();
 
-					if !self.valid_game_folders.is_empty() {
-						if self.valid_game_folders.len() == 1 {
-							ui.label(
-								RichText::from(if self.manually_selected_folder {
-									"✅ Game folder manually selected"
-								} else {
-									"✅ Game folder found automatically"
-								})
-								.size(7.0)
-							);
-						} else {
-							ComboBox::from_label(
-								RichText::from("Select your game folder").size(7.0)
-							)
-							.selected_text(
-								RichText::from(if let Some(x) = self.selected_game_folder {
-									String::from(
-										self.valid_game_folders
-											.get(x)
-											.context("selected_game_folder")?
-											.0
-											.to_string_lossy()
-									)
-								} else {
-									"".to_owned()
-								})
-								.size(7.0)
-							)
-							.width(200.0)
-							.show_ui(ui, |ui| {
-								for (ind, (folder, _)) in self.valid_game_folders.iter().enumerate()
-								{
-									ui.selectable_value(
-										&mut self.selected_game_folder,
-										Some(ind),
-										RichText::from(folder.to_string_lossy()).size(7.0)
-									);
+										ui.label(
+											RichText::from(if let Some(x) = location {
+												format!("Searching {}", x.display())
+											} else {
+												"Searching your drives".to_owned()
+											})
+											.size(7.0)
+										);
+
+										if !cancelled
+											&& ui
+												.button(RichText::from("Cancel").size(7.0))
+												.clicked()
+										{
+											if let Some(search) = &mut self.search {
+												search.cancel();
+											}
+										}
+									});
 								}
-							});
-						}
-					} else if !self.already_installed_folders.is_empty() {
-						ui.label(
-							RichText::from(
-								"The framework is already installed! If you're trying to update \
-								 the framework, use the Mod Manager's integrated update \
-								 functionality, which you can find by opening the Mod Manager and \
-								 looking at the home page."
-							)
-							.size(7.0)
-						);
-					} else {
-						ui.label(
-							RichText::from(
-								"We couldn't find HITMAN 3 anywhere. Make sure you're trying to \
-								 install the framework on a copy of HITMAN 3 installed via Steam, \
-								 Epic Games Launcher/Legendary or the Xbox app, then select your \
-								 folder manually below."
-							)
-							.size(7.0)
-						);
 
-						ui.add_space(5.0);
+								DriveScanStatus::Cancelled => {
+									ui.label(RichText::from("Search cancelled").size(7.0));
+								}
 
-						if ui
-							.button(RichText::from("Select your game folder").size(7.0))
-							.clicked()
-						{
-							if let Some(folder) = FileDialog::new()
-								.set_title(
-									"Select your game folder; it should contain a folder called \
-									 Retail"
-								)
-								.pick_folder()
-							{
-								self.performed_automatic_check = false;
-								self.manually_selected_folder = true;
-								self.check_paths = vec![(folder, None)];
+								DriveScanStatus::Finished if self.games.installable.is_empty() => {
+									ui.label(
+										RichText::from(
+											"We searched your drives but couldn't find HITMAN 3; \
+											 select your folder manually instead."
+										)
+										.size(7.0)
+									);
+								}
+
+								DriveScanStatus::Finished => {
+									ui.label(
+										RichText::from("✅ Finished searching your drives")
+											.size(7.0)
+									);
+								}
 							}
 						}
-					}
 
-					Ok(())
-				})();
+						Ok(())
+					})();
 
-				if let Err(x) = x {
-					self.error = Some(format!("{x:?}"));
-				}
+					if let Err(x) = x {
+						self.transition(WizardEvent::Failed(InstallerError::internal(x)));
+					}
 
-				ui.add_space(5.0);
+					ui.add_space(5.0);
 
-				if self.selected_game_folder.is_some() {
+					if ui
+						.add_enabled(
+							matches!(
+								self.state,
+								WizardState::Choosing {
+									selected: Some(_),
+									..
+								}
+							),
+							Button::new(RichText::from("Next").size(7.0))
+						)
+						.clicked()
+					{
+						self.transition(WizardEvent::Next);
+					}
+				} else if self.selected_folder().is_some() {
 					ui.label("Ready to install the framework?");
 
-					if let Some(download_size) = self.download_size {
+					// Options can only be changed before installing starts
+					let editable = matches!(self.state, WizardState::Options { .. });
+
+					let fetched = self.release.as_ref().map_or(false, |x| x.ready().is_some());
+					let framework_version = self.release().and_then(|x| x.version.to_owned());
+					let download_size = self.release().and_then(|x| x.size).map(|x| x as f64);
+
+					if !fetched {
+						ui.horizontal_wrapped(|ui| {
+							ui.spinner();
+							ui.label(
+								RichText::from("Checking for the latest release...").size(7.0)
+							);
+						});
+					} else if let Some(download_size) = download_size {
 						ui.label(
 							RichText::from(format!(
 								"This will download {} of data.",
@@ -552,36 +1070,340 @@ impl eframe::App for App {
 							.size(8.0)
 						);
 
-						ui.horizontal_wrapped(|ui| {
-							if let Some(selected_game_folder) = self.selected_game_folder {
-								if self.download_promise.is_none()
-									&& ui
-										.button(RichText::from("Install the framework").size(7.0))
-										.clicked()
+						let compatibility = check_compatibility(
+							framework_version.as_deref(),
+							self.selected_folder().and_then(|x| x.game_version)
+						);
+
+						if let Compatibility::Unsupported(game_versions) = &compatibility {
+							ui.label(
+								RichText::from(format!(
+									"⚠ This release of the framework ({}) only supports game \
+									 versions {}; your game is version {}. Mods may not work \
+									 until the framework or the game is updated.",
+									framework_version.as_deref().unwrap_or_default(),
+									game_versions.join(", "),
+									self.selected_folder()
+										.and_then(|x| x.game_version)
+										.map(|x| x.to_string())
+										.unwrap_or_default()
+								))
+								.color(Color32::from_rgb(220, 150, 30))
+								.size(7.0)
+							);
+
+							if editable {
+								ui.checkbox(
+									&mut self.accepted_unsupported_game_version,
+									RichText::from("Install anyway").size(7.0)
+								);
+							}
+						}
+
+						let artifacts = self
+							.selected_folder()
+							.map(|x| x.artifacts.to_owned())
+							.unwrap_or_default();
+
+						if !artifacts.is_empty() {
+							ui.label(
+								RichText::from(
+									"⚠ This copy of the game has already been modified by other \
+									 tools:"
+								)
+								.color(Color32::from_rgb(220, 150, 30))
+								.size(7.0)
+							);
+
+							for artifact in &artifacts {
+								ui.label(
+									RichText::from(format!(
+										"    {}: {}",
+										artifact.path.display(),
+										artifact.kind.explanation()
+									))
+									.size(6.0)
+								);
+							}
+
+							if artifacts.iter().any(|x| !x.kind.can_back_up()) {
+								ui.label(
+									RichText::from(
+										"Edited game files can't be moved; verify the game files \
+										 through your store to restore them."
+									)
+									.size(6.0)
+								);
+							}
+
+							if editable && artifacts.iter().any(|x| x.kind.can_back_up()) {
+								ui.checkbox(
+									&mut self.back_up_conflicts,
+									RichText::from(format!(
+										"Move them into a \"{BACKUP_FOLDER}\" folder in the game \
+										 folder before installing"
+									))
+									.size(7.0)
+								);
+							}
+						}
+
+						if let Some(folder) = self
+							.state
+							.folder()
+							.and_then(|x| self.games.installable.get_mut(x))
+						{
+							match &self.preflight_promise {
+								Some((path, promise)) if *path == folder.path => {
+									if let Some(preflight) = promise.ready() {
+										folder.preflight = Some(preflight.to_owned());
+										self.preflight_promise = None;
+									}
+								}
+
+								_ if folder.preflight.is_none() => {
+									let ctx = ctx.clone();
+									let path = folder.path.to_owned();
+
+									self.preflight_promise = Some((
+										path.to_owned(),
+										Promise::spawn_thread("preflight", move || {
+											let preflight = Preflight::check(&path);
+											ctx.request_repaint();
+											preflight
+										})
+									));
+								}
+
+								_ => {}
+							}
+						}
+
+						let preflight = self.selected_folder().and_then(|x| x.preflight.as_ref());
+
+						let checking_folder = preflight.is_none();
+
+						// The download size is a lower bound for the extracted size; the exact size
+						// is checked again from the archive when installing
+						let preflight_problems = preflight
+							.map(|x| x.problems_for(download_size as u64))
+							.unwrap_or_default();
+
+						if checking_folder {
+							ui.horizontal_wrapped(|ui| {
+								ui.spinner();
+								ui.label(RichText::from("Checking the game folder...").size(7.0));
+							});
+						}
+
+						if !preflight_problems.is_empty() {
+							for problem in &preflight_problems {
+								ui.label(if problem.is_blocking() {
+									RichText::from(format!("❌ {}", problem.explanation()))
+										.color(Color32::from_rgb(200, 50, 50))
+										.size(7.0)
+								} else {
+									RichText::from(format!("⚠ {}", problem.explanation()))
+										.color(Color32::from_rgb(220, 150, 30))
+										.size(7.0)
+								});
+							}
+
+							if editable
+								&& ui.button(RichText::from("Check again").size(7.0)).clicked()
+							{
+								if let Some(folder) = self
+									.state
+									.folder()
+									.and_then(|x| self.games.installable.get_mut(x))
+								{
+									folder.preflight = None;
+								}
+							}
+						}
+
+						// Files that are in use can't be replaced, so everything running from the
+						// game folder has to be closed first; checked again right before writing
+						let running = match self
+							.state
+							.folder()
+							.and_then(|x| self.games.installable.get(x))
+						{
+							Some(folder) if !matches!(self.state, WizardState::Done { .. }) => {
+								if self
+									.process_watch
+									.as_ref()
+									.map_or(false, |x| x.game_folder != folder.path)
 								{
-									self.download_promise.get_or_insert_with(|| {
+									self.process_watch = None;
+								}
+
+								self.process_watch
+									.get_or_insert_with(|| {
 										let ctx = ctx.clone();
-										let (sender, promise) = Promise::new();
+										ProcessWatch::start(&folder.path, move || {
+											ctx.request_repaint()
+										})
+									})
+									.running()
+							}
+
+							_ => Some(vec![])
+						};
 
-										let request = ehttp::Request::get(FRAMEWORK_DOWNLOAD_URL);
+						// Nothing can be said about what's running until the first check finishes
+						let processes_checked = running.is_some();
+						let running = running.unwrap_or_default();
 
-										ehttp::fetch(request, move |response| {
-											let data = response.map(|x| x.bytes);
-											sender.send(data);
+						if !running.is_empty() {
+							ui.label(
+								RichText::from(format!(
+									"⚠ {} is running from the game folder and has to be closed \
+									 before the framework can be installed; installation will \
+									 continue once it is.",
+									process_names(&running)
+								))
+								.color(Color32::from_rgb(220, 150, 30))
+								.size(7.0)
+							);
+
+							if ui.button(RichText::from("Close it").size(7.0)).clicked() {
+								request_close(&running);
+							}
+						}
+
+						if let Some((prerequisite, promise)) = &self.prerequisite_promise {
+							if let Some(result) = promise.ready() {
+								self.prerequisite_error = result.as_ref().err().map(|x| {
+									format!("{} couldn't be installed: {x}", prerequisite.name)
+								});
+
+								self.missing_prerequisites = missing_prerequisites();
+								self.prerequisite_promise = None;
+							}
+						}
+
+						for &prerequisite in &self.missing_prerequisites {
+							ui.label(
+								RichText::from(format!(
+									"⚠ {} isn't installed; {}.",
+									prerequisite.name, prerequisite.reason
+								))
+								.color(Color32::from_rgb(220, 150, 30))
+								.size(7.0)
+							);
+
+							ui.horizontal_wrapped(|ui| {
+								if ui
+									.add_enabled(
+										self.prerequisite_promise.is_none(),
+										Button::new(
+											RichText::from(format!(
+												"Install {}",
+												prerequisite.name
+											))
+											.size(7.0)
+										)
+									)
+									.clicked()
+								{
+									let ctx = ctx.clone();
+
+									self.prerequisite_promise = Some((
+										prerequisite,
+										Promise::spawn_thread("prerequisite", move || {
+											let result = prerequisite
+												.install()
+												.map_err(|x| format!("{x:#}"));
 											ctx.request_repaint();
-										});
+											result
+										})
+									));
+								}
 
-										promise
-									});
+								if self
+									.prerequisite_promise
+									.as_ref()
+									.map_or(false, |(x, _)| x.name == prerequisite.name)
+								{
+									ui.spinner();
+								}
+							});
+						}
+
+						if let Some(error) = &self.prerequisite_error {
+							ui.label(
+								RichText::from(format!("❌ {error}"))
+									.color(Color32::from_rgb(200, 50, 50))
+									.size(7.0)
+							);
+						}
+
+						if editable {
+							ui.horizontal_wrapped(|ui| {
+								ui.checkbox(
+									&mut self.start_menu_shortcut,
+									RichText::from(format!(
+										"Add a shortcut to {}",
+										ShortcutLocation::StartMenu.name()
+									))
+									.size(7.0)
+								);
+
+								ui.checkbox(
+									&mut self.desktop_shortcut,
+									RichText::from("Add a desktop shortcut").size(7.0)
+								);
+
+								ui.checkbox(
+									&mut self.launch_after_install,
+									RichText::from("Open the Mod Manager when done").size(7.0)
+								);
+
+								if cfg!(windows) {
+									ui.checkbox(
+										&mut self.all_users_shortcuts,
+										RichText::from("For everyone on this PC").size(7.0)
+									);
+								}
+							});
+						}
+
+						let can_install = (!matches!(compatibility, Compatibility::Unsupported(_))
+							|| self.accepted_unsupported_game_version)
+							&& !checking_folder && !preflight_problems
+							.iter()
+							.any(|x| x.is_blocking())
+							&& processes_checked && running.is_empty();
+
+						ui.horizontal_wrapped(|ui| {
+							if let Some(selected_game_folder) = self.state.folder() {
+								if editable {
+									if ui.button(RichText::from("Back").size(7.0)).clicked() {
+										self.transition(WizardEvent::Back);
+									}
+
+									if ui
+										.add_enabled(
+											can_install,
+											Button::new(
+												RichText::from("Install the framework").size(7.0)
+											)
+										)
+										.clicked()
+									{
+										self.transition(WizardEvent::Next);
+									}
 								}
 
 								let x = (|| -> anyhow::Result<()> {
 									ui.label(
 										RichText::from(
-											self.valid_game_folders
+											self.games
+												.installable
 												.get(selected_game_folder)
-												.context("selected_game_folder")?
-												.0
+												.context("selected game folder")?
+												.path
 												.to_str()
 												.context("game folder to_str")?
 										)
@@ -590,15 +1412,16 @@ impl eframe::App for App {
 
 									ui.label(
 										RichText::from(
-											if let Some(s) = &self
-												.valid_game_folders
+											if let Some(s) = self
+												.games
+												.installable
 												.get(selected_game_folder)
-												.context("selected_game_folder")?
-												.1
+												.context("selected game folder")?
+												.username()
 											{
 												format!("✅ Hello, {}!", s)
 											} else {
-												if self.valid_game_folders.len() == 1 {
+												if self.games.installable.len() == 1 {
 													if self.manually_selected_folder {
 														"✅ Game folder manually selected"
 													} else {
@@ -617,100 +1440,286 @@ impl eframe::App for App {
 								})();
 
 								if let Err(x) = x {
-									self.error = Some(format!("{x:?}"));
+									self.transition(WizardEvent::Failed(InstallerError::internal(
+										x
+									)));
 								}
 							}
 						});
 
+						if let Some(folder) = self
+							.state
+							.folder()
+							.and_then(|x| self.games.installable.get_mut(x))
+						{
+							ui.horizontal_wrapped(|ui| {
+								ui.label(
+									RichText::from(match folder.platform() {
+										Some(guess) => format!(
+											"Store: {} ({} confidence; {})",
+											guess.platform.name(),
+											guess.confidence.name(),
+											guess.evidence.join(", ")
+										),
+										None => "Store: unknown, please select it".to_owned()
+									})
+									.size(7.0)
+								);
+
+								if editable {
+									ComboBox::from_id_source("platform_override")
+										.selected_text(
+											RichText::from(
+												folder
+													.platform_override
+													.map_or("Detect automatically", |x| x.name())
+											)
+											.size(7.0)
+										)
+										.show_ui(ui, |ui| {
+											ui.selectable_value(
+												&mut folder.platform_override,
+												None,
+												RichText::from("Detect automatically").size(7.0)
+											);
+
+											for platform in Platform::ALL {
+												ui.selectable_value(
+													&mut folder.platform_override,
+													Some(platform),
+													RichText::from(platform.name()).size(7.0)
+												);
+											}
+										});
+								}
+							});
+						}
+
+						if let Some(runtime) =
+							self.selected_folder().and_then(|x| x.wine_runtime.as_ref())
+						{
+							ui.label(
+								RichText::from(format!(
+									"The Mod Manager will run through {}.",
+									runtime.description()
+								))
+								.size(7.0)
+							);
+						}
+
 						ui.add_space(5.0);
 
-						if let Some(promise) = &self.download_promise {
-							match promise.ready() {
-								None => {
+						match self.state {
+							WizardState::Downloading { .. } => {
+								let received = self.download_received.load(Ordering::Relaxed);
+
+								ui.horizontal_wrapped(|ui| {
 									ui.spinner();
+									ui.label(
+										RichText::from(format!(
+											"Downloading... {} of {}",
+											human_bytes(received as f64),
+											human_bytes(download_size)
+										))
+										.size(7.0)
+									);
+
+									if ui.button(RichText::from("Cancel").size(7.0)).clicked() {
+										self.download_cancel.cancel();
+									}
+								});
+
+								let event = match &self.state {
+									WizardState::Downloading {
+										download: Some(download),
+										..
+									} => match download.ready() {
+										None => None,
+										Some(Ok(download)) => {
+											Some(WizardEvent::Downloaded(download.to_owned()))
+										}
+										Some(Err(InstallerError::Cancelled)) => {
+											Some(WizardEvent::Cancel)
+										}
+										Some(Err(e)) => Some(WizardEvent::Failed(e.to_owned()))
+									},
+
+									_ => None
+								};
+
+								if let Some(event) = event {
+									self.transition(event);
 								}
-								Some(data) => {
-									if !self.installation_done {
-										match data {
-											Ok(data) => {
-												match (|| -> anyhow::Result<()> {
-													extract(
-														Cursor::new(data),
-														&self
-															.valid_game_folders
-															.get(
-																self.selected_game_folder.context(
-																	"selected_game_folder"
-																)?
-															)
-															.context("game folder index")?
-															.0
-															.join("Simple Mod Framework"),
-														false
-													)?;
-
-													ShellLink::new(
-														self.valid_game_folders
-															.get(
-																self.selected_game_folder.context(
-																	"selected_game_folder"
-																)?
-															)
-															.context("game folder index")?
-															.0
-															.join("Simple Mod Framework")
-															.join("Mod Manager")
-															.join("Mod Manager.exe")
-															.to_str()
-															.context("linktarget to_str")?
-													)?
-													.create_lnk(
-														home_dir()
-															.context("home dir")?
-															.join("AppData")
-															.join("Roaming")
-															.join("Microsoft")
-															.join("Windows")
-															.join("Start Menu")
-															.join("Programs")
-															.join("Simple Mod Framework.lnk")
-													)?;
-
-													Ok(())
-												})()
-												.context("Extracting/creating link")
-												{
-													Ok(_) => {
-														self.installation_done = true;
-													}
+							}
 
-													Err(e) => {
-														self.error = Some(format!("{e:?}"));
+							// Waits here while anything is running from the game folder, which the
+							// warning above explains
+							WizardState::Verifying { .. } => {
+								ui.spinner();
+
+								let event = match &mut self.state {
+									WizardState::Verifying {
+										plan: Some(plan), ..
+									} => plan.ready().map(|x| {
+										match x.as_ref().map(|x| x.blocker()) {
+											Ok(None) => WizardEvent::Verified,
+											Ok(Some(e)) => WizardEvent::Failed(e),
+											Err(e) => WizardEvent::Failed(e.to_owned())
+										}
+									}),
+
+									// Reads the whole archive and the game folder, so it's done on
+									// a worker
+									WizardState::Verifying {
+										folder,
+										download,
+										plan
+									} if processes_checked && running.is_empty() => {
+										match self.games.installable.get(*folder) {
+											Some(folder) => {
+												let ctx = ctx.clone();
+												let download = download.to_owned();
+												let game_folder = folder.path.to_owned();
+
+												*plan = Some(Promise::spawn_thread(
+													"verify",
+													move || {
+														let result =
+															plan_install(&download, &game_folder);
+														ctx.request_repaint();
+														result
 													}
-												};
+												));
+
+												None
 											}
 
-											Err(e) => {
-												self.error = Some(format!("{e:?}"));
+											None => {
+												Some(WizardEvent::Failed(InstallerError::internal(
+													anyhow::anyhow!("No game folder to verify")
+												)))
 											}
 										}
 									}
 
-									if self.installation_done {
-										ui.add_space(4.0);
+									_ => None
+								};
 
-										ui.label("Installation done!");
+								if let Some(event) = event {
+									self.transition(event);
+								}
+							}
 
-										ui.label(
-											RichText::from(
+							WizardState::Installing { .. } => {
+								let step = self.step_description("Installing...");
+
+								ui.horizontal_wrapped(|ui| {
+									ui.spinner();
+									ui.label(RichText::from(step).size(7.0));
+
+									if ui.button(RichText::from("Cancel").size(7.0)).clicked() {
+										self.install_cancel.cancel();
+									}
+								});
+
+								let result = match &self.state {
+									WizardState::Installing {
+										install: Some(install),
+										..
+									} => install.ready().cloned(),
+
+									_ => None
+								};
+
+								match result {
+									Some(Ok(_)) => {
+										self.transition(WizardEvent::Installed);
+
+										if self.launch_after_install {
+											self.launch_mod_manager();
+										}
+									}
+
+									Some(Err(InstallerError::Cancelled)) => {
+										self.transition(WizardEvent::Cancel);
+									}
+
+									Some(Err(e)) => {
+										tracing::error!("Install failed: {e}\n{}", e.detail());
+										self.transition(WizardEvent::Failed(e));
+									}
+
+									None => {}
+								}
+							}
+
+							WizardState::Done { .. } => {
+								ui.add_space(4.0);
+
+								ui.label("Installation done!");
+
+								ui.label(
+									RichText::from(
+										match (self.start_menu_shortcut, self.desktop_shortcut) {
+											(true, true) => format!(
+												"You can close this window; shortcuts have been \
+												 added to {} and {}.",
+												ShortcutLocation::StartMenu.name(),
+												ShortcutLocation::Desktop.name()
+											),
+											(true, false) => format!(
+												"You can close this window; a shortcut has been \
+												 added to {}.",
+												ShortcutLocation::StartMenu.name()
+											),
+											(false, true) => format!(
 												"You can close this window; a shortcut has been \
-												 added to the Start menu."
+												 added to {}.",
+												ShortcutLocation::Desktop.name()
+											),
+											(false, false) => String::from(
+												"You can close this window; the Mod Manager is in \
+												 the Simple Mod Framework folder in the game \
+												 folder."
 											)
-											.size(8.0)
-										);
+										}
+									)
+									.size(8.0)
+								);
+
+								ui.add_space(2.5);
+
+								ui.horizontal_wrapped(|ui| {
+									if ui
+										.button(RichText::from("Launch Mod Manager").size(7.0))
+										.clicked()
+									{
+										self.launch_mod_manager();
 									}
-								}
+
+									match &self.launch_result {
+										Some(Ok(_)) => {
+											ui.label(
+												RichText::from("✅ Mod Manager opened").size(7.0)
+											);
+										}
+
+										Some(Err(e)) => {
+											ui.label(
+												RichText::from(format!(
+													"❌ The Mod Manager couldn't be opened: {e}"
+												))
+												.color(Color32::from_rgb(200, 50, 50))
+												.size(7.0)
+											);
+										}
+
+										None => {}
+									}
+								});
 							}
+
+							_ => {}
 						}
 					} else {
 						ui.label(
@@ -724,5 +1733,8 @@ impl eframe::App for App {
 				}
 			});
 		}
+
+		// Whatever this frame moved on to starts straight away
+		self.start_pending_work(ctx);
 	}
 }
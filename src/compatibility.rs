@@ -0,0 +1,153 @@
+use crate::game_version::GameVersion;
+
+/// The game builds a framework release supports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameworkRelease {
+	/// The `major.minor` version of the release, which covers all of its patch releases.
+	pub version: &'static str,
+
+	/// The `major.minor` file versions of HITMAN3.exe the release works with.
+	pub game_versions: &'static [&'static str]
+}
+
+/// The game builds each framework release supports, newest release first. Add a row when a
+/// framework release changes which game updates it supports, going by its release notes; releases
+/// that aren't listed get no warning either way.
+pub const RELEASES: &[FrameworkRelease] = &[];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Compatibility {
+	Supported,
+
+	/// The game versions the framework release supports instead.
+	Unsupported(Vec<String>),
+
+	/// The release isn't in the table, or the game version isn't known, so nothing can be said
+	/// either way.
+	Unknown
+}
+
+/// Get the release version from a GitHub release asset URL, which the latest release download
+/// redirects to (`.../releases/download/v2.33.13/Release.zip` gives `2.33.13`).
+pub fn release_version_from_url(url: &str) -> Option<String> {
+	let (_, rest) = url.split_once("/releases/download/")?;
+	let tag = rest.split('/').next()?;
+
+	Some(tag.trim_start_matches('v').to_owned()).filter(|x| !x.is_empty())
+}
+
+/// Find the row for a release version (like `2.33.13`) in a table of releases.
+fn find_release<'a>(
+	releases: &'a [FrameworkRelease],
+	framework_version: &str
+) -> Option<&'a FrameworkRelease> {
+	let mut parts = framework_version.split('.');
+	let major_minor = format!("{}.{}", parts.next()?, parts.next()?);
+
+	releases.iter().find(|x| x.version == major_minor)
+}
+
+fn check_against(
+	releases: &[FrameworkRelease],
+	framework_version: Option<&str>,
+	game_version: Option<GameVersion>
+) -> Compatibility {
+	match (
+		framework_version.and_then(|x| find_release(releases, x)),
+		game_version
+	) {
+		(Some(release), Some(game_version)) => {
+			let game_version = format!("{}.{}", game_version.major, game_version.minor);
+
+			if release.game_versions.contains(&game_version.as_str()) {
+				Compatibility::Supported
+			} else {
+				Compatibility::Unsupported(
+					release
+						.game_versions
+						.iter()
+						.map(|x| x.to_string())
+						.collect()
+				)
+			}
+		}
+
+		_ => Compatibility::Unknown
+	}
+}
+
+/// Compare the game's version against the game versions a framework release supports.
+pub fn check_compatibility(
+	framework_version: Option<&str>,
+	game_version: Option<GameVersion>
+) -> Compatibility {
+	check_against(RELEASES, framework_version, game_version)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TEST_RELEASES: &[FrameworkRelease] = &[
+		FrameworkRelease {
+			version: "2.33",
+			game_versions: &["3.170", "3.180"]
+		},
+		FrameworkRelease {
+			version: "2.32",
+			game_versions: &["3.170"]
+		}
+	];
+
+	fn game_version(major: u16, minor: u16) -> Option<GameVersion> {
+		Some(GameVersion {
+			major,
+			minor,
+			patch: 0,
+			build: 0
+		})
+	}
+
+	#[test]
+	fn reads_release_version_from_url() {
+		assert_eq!(
+			release_version_from_url(
+				"https://github.com/atampy25/simple-mod-framework/releases/download/v2.33.13/\
+				 Release.zip"
+			),
+			Some("2.33.13".to_owned())
+		);
+		assert_eq!(
+			release_version_from_url("https://example.com/Release.zip"),
+			None
+		);
+	}
+
+	#[test]
+	fn checks_game_version_against_release() {
+		assert_eq!(
+			check_against(TEST_RELEASES, Some("2.33.13"), game_version(3, 180)),
+			Compatibility::Supported
+		);
+		assert_eq!(
+			check_against(TEST_RELEASES, Some("2.32.1"), game_version(3, 180)),
+			Compatibility::Unsupported(vec!["3.170".to_owned()])
+		);
+	}
+
+	#[test]
+	fn unknown_without_release_or_game_version() {
+		assert_eq!(
+			check_against(TEST_RELEASES, Some("2.31.0"), game_version(3, 170)),
+			Compatibility::Unknown
+		);
+		assert_eq!(
+			check_against(TEST_RELEASES, None, game_version(3, 170)),
+			Compatibility::Unknown
+		);
+		assert_eq!(
+			check_against(TEST_RELEASES, Some("2.33.13"), None),
+			Compatibility::Unknown
+		);
+	}
+}
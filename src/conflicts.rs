@@ -0,0 +1,179 @@
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	time::{SystemTime, UNIX_EPOCH}
+};
+
+use anyhow::Context;
+
+use crate::game_folder::runtime_folder;
+
+/// The first bytes of the encrypted packagedefinition.txt and thumbs.dat the game ships with.
+/// Modding tools replace them with decrypted, edited copies.
+pub const ENCRYPTED_HEADER: [u8; 8] = [0x22, 0x3D, 0x6F, 0x9A, 0xB3, 0xF8, 0xFE, 0xB6];
+
+/// The game itself only ships low patch levels; anything from this level up was added by a tool.
+pub const FIRST_MODDED_PATCH_LEVEL: u32 = 10;
+
+/// Files and folders mod loaders put next to HITMAN3.exe.
+const LOADER_FILES: &[&str] = &["dinput8.dll", "ZHMModSDK.dll", "mods.toml", "mods"];
+
+/// The folder in the game folder that conflicting files are moved into.
+pub const BACKUP_FOLDER: &str = "Simple Mod Framework Installer Backup";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtifactKind {
+	PatchChunk,
+	EditedPackageDefinition,
+	EditedThumbs,
+	ModLoader
+}
+
+impl ArtifactKind {
+	/// How the artifact affects the framework.
+	pub fn explanation(&self) -> &'static str {
+		match self {
+			ArtifactKind::PatchChunk => {
+				"a patch chunk added by another mod tool; the framework manages its own patches \
+				 and may overwrite this one or have its mods overridden by it"
+			}
+			ArtifactKind::EditedPackageDefinition => {
+				"packagedefinition.txt has been edited by another tool; the framework edits it \
+				 itself when deploying, so the other tool's changes will be lost"
+			}
+			ArtifactKind::EditedThumbs => {
+				"thumbs.dat has been edited by another tool; the framework edits it itself when \
+				 deploying, so the other tool's changes will be lost"
+			}
+			ArtifactKind::ModLoader => {
+				"part of a mod loader such as ZHMModSDK; it can be used alongside the framework, \
+				 but its mods aren't managed by it and can cause crashes the framework can't \
+				 explain"
+			}
+		}
+	}
+
+	/// Whether the artifact can be moved out of the way without breaking the game. Edited config
+	/// files have to be restored by verifying the game files instead.
+	pub fn can_back_up(&self) -> bool {
+		!matches!(
+			self,
+			ArtifactKind::EditedPackageDefinition | ArtifactKind::EditedThumbs
+		)
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Artifact {
+	pub kind: ArtifactKind,
+	pub path: PathBuf
+}
+
+/// Parse an rpkg file name like `chunk0patch3.rpkg` into its chunk and patch level.
+pub fn parse_chunk_name(name: &str) -> Option<(u32, Option<u32>)> {
+	let name = name.to_lowercase();
+	let rest = name.strip_prefix("chunk")?.strip_suffix(".rpkg")?;
+
+	match rest.split_once("patch") {
+		Some((chunk, patch)) => Some((chunk.parse().ok()?, Some(patch.parse().ok()?))),
+		None => Some((rest.parse().ok()?, None))
+	}
+}
+
+/// Whether a config file is still the encrypted copy the game shipped with.
+pub fn is_encrypted(path: &Path) -> bool {
+	fs::read(path)
+		.map(|x| x.starts_with(&ENCRYPTED_HEADER))
+		.unwrap_or(true)
+}
+
+/// Find everything in the game folder left behind by other mod tools.
+pub fn scan_game_folder(game_folder: &Path) -> Vec<Artifact> {
+	let mut artifacts = vec![];
+
+	let runtime = runtime_folder(game_folder);
+
+	if let Ok(entries) = fs::read_dir(&runtime) {
+		let mut chunks = entries
+			.filter_map(|x| x.ok())
+			.filter(|x| {
+				parse_chunk_name(&x.file_name().to_string_lossy())
+					.and_then(|(_, patch)| patch)
+					.map_or(false, |x| x >= FIRST_MODDED_PATCH_LEVEL)
+			})
+			.map(|x| Artifact {
+				kind: ArtifactKind::PatchChunk,
+				path: x.path()
+			})
+			.collect::<Vec<_>>();
+
+		chunks.sort_by(|a, b| a.path.cmp(&b.path));
+		artifacts.extend(chunks);
+	}
+
+	if runtime.join("packagedefinition.txt").is_file()
+		&& !is_encrypted(&runtime.join("packagedefinition.txt"))
+	{
+		artifacts.push(Artifact {
+			kind: ArtifactKind::EditedPackageDefinition,
+			path: runtime.join("packagedefinition.txt")
+		});
+	}
+
+	let thumbs = game_folder.join("Retail").join("thumbs.dat");
+
+	if thumbs.is_file() && !is_encrypted(&thumbs) {
+		artifacts.push(Artifact {
+			kind: ArtifactKind::EditedThumbs,
+			path: thumbs
+		});
+	}
+
+	for file in LOADER_FILES {
+		let path = game_folder.join("Retail").join(file);
+
+		if path.exists() {
+			artifacts.push(Artifact {
+				kind: ArtifactKind::ModLoader,
+				path
+			});
+		}
+	}
+
+	artifacts
+}
+
+/// Move every artifact that can be moved into a new backup folder in the game folder, keeping
+/// their paths relative to the game folder. Returns the backup folder.
+pub fn back_up(game_folder: &Path, artifacts: &[Artifact]) -> anyhow::Result<PathBuf> {
+	let backup_folder = game_folder.join(BACKUP_FOLDER).join(
+		SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.context("System time")?
+			.as_secs()
+			.to_string()
+	);
+
+	for artifact in artifacts.iter().filter(|x| x.kind.can_back_up()) {
+		let destination = backup_folder.join(
+			artifact
+				.path
+				.strip_prefix(game_folder)
+				.context("Artifact outside game folder")?
+		);
+
+		fs::create_dir_all(destination.parent().context("Backup destination parent")?)
+			.context("Creating backup folder")?;
+
+		fs::rename(&artifact.path, &destination).with_context(|| {
+			format!(
+				"Moving {} to {}",
+				artifact.path.display(),
+				destination.display()
+			)
+		})?;
+	}
+
+	Ok(backup_folder)
+}
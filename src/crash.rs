@@ -0,0 +1,151 @@
+use std::{
+	backtrace::Backtrace,
+	fmt, fs, panic,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
+	time::{SystemTime, UNIX_EPOCH}
+};
+
+use anyhow::Context;
+use rfd::{MessageButtons, MessageDialog, MessageLevel};
+
+use crate::logging::{log_folder, Log};
+
+/// How many of the most recent log lines go in a crash report.
+const REPORT_LOG_LINES: usize = 100;
+
+/// Keeps what a crash report needs to say about what the installer was doing.
+#[derive(Clone)]
+pub struct CrashReporter {
+	log: Log,
+	selected_folder: Arc<Mutex<Option<PathBuf>>>
+}
+
+impl CrashReporter {
+	pub fn set_selected_folder(&self, folder: Option<&Path>) {
+		if let Ok(mut x) = self.selected_folder.lock() {
+			if x.as_deref() != folder {
+				*x = folder.map(|x| x.to_owned());
+			}
+		}
+	}
+
+	fn report(&self, info: &dyn fmt::Display) -> String {
+		let selected_folder = self
+			.selected_folder
+			.try_lock()
+			.ok()
+			.and_then(|x| x.as_ref().map(|x| x.display().to_string()))
+			.unwrap_or_else(|| "none".to_owned());
+
+		// The panic may have happened while the log was locked on this thread
+		let log = match self.log.try_recent_lines() {
+			Some(log) => log[log.len().saturating_sub(REPORT_LOG_LINES)..].join("\n"),
+			None => "(the log was in use, so it isn't included)".to_owned()
+		};
+
+		format!(
+			"Simple Mod Framework installer crash report\n\nInstaller version: {}\nSystem: {} \
+			 {}\nSelected game folder: {selected_folder}\n\n{info}\n\nBacktrace:\n{}\n\nRecent \
+			 log:\n{}\n",
+			env!("CARGO_PKG_VERSION"),
+			std::env::consts::OS,
+			std::env::consts::ARCH,
+			Backtrace::force_capture(),
+			log
+		)
+	}
+}
+
+/// Where crash reports the user hasn't been told about yet are kept; once they have, they're moved
+/// up to the crashes folder.
+fn new_crash_folder() -> Option<PathBuf> {
+	crash_folder().map(|x| x.join("new"))
+}
+
+pub fn crash_folder() -> Option<PathBuf> {
+	log_folder().map(|x| x.join("crashes"))
+}
+
+/// Write a crash report and tell the user where it is whenever the installer panics.
+pub fn install_crash_handler(log: Log) -> CrashReporter {
+	let reporter = CrashReporter {
+		log,
+		selected_folder: Arc::new(Mutex::new(None))
+	};
+
+	let hook_reporter = reporter.to_owned();
+	let default_hook = panic::take_hook();
+
+	panic::set_hook(Box::new(move |info| {
+		default_hook(info);
+
+		let report = hook_reporter.report(info);
+
+		let description = match write_crash_report(&report) {
+			Ok(path) => format!(
+				"The installer crashed. A crash report was saved to {}; please include it when \
+				 asking for help on Hitman Forum (not Nexus Mods).",
+				path.display()
+			),
+
+			Err(e) => format!(
+				"The installer crashed, and the crash report couldn't be saved \
+				 ({e:#}).\n\n{report}"
+			)
+		};
+
+		MessageDialog::new()
+			.set_level(MessageLevel::Error)
+			.set_title("The installer crashed")
+			.set_description(&description)
+			.set_buttons(MessageButtons::Ok)
+			.show();
+	}));
+
+	reporter
+}
+
+fn write_crash_report(report: &str) -> anyhow::Result<PathBuf> {
+	let folder = new_crash_folder().context("No data folder")?;
+
+	fs::create_dir_all(&folder).context("Creating crash report folder")?;
+
+	let path = folder.join(format!(
+		"crash-{}.txt",
+		SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|x| x.as_secs())
+			.unwrap_or_default()
+	));
+
+	fs::write(&path, report).context("Writing crash report")?;
+
+	Ok(path)
+}
+
+/// Crash reports from previous runs the user hasn't been told about.
+pub fn new_crash_reports() -> Vec<PathBuf> {
+	let mut reports = new_crash_folder()
+		.and_then(|x| fs::read_dir(x).ok())
+		.into_iter()
+		.flatten()
+		.filter_map(|x| x.ok())
+		.map(|x| x.path())
+		.filter(|x| x.is_file())
+		.collect::<Vec<_>>();
+
+	reports.sort();
+	reports
+}
+
+/// Move crash reports out of the new folder once the user's seen them.
+pub fn mark_crash_reports_seen(reports: &[PathBuf]) {
+	if let Some(folder) = crash_folder() {
+		for report in reports {
+			if let Some(name) = report.file_name() {
+				let _ = fs::rename(report, folder.join(name));
+			}
+		}
+	}
+}
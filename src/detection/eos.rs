@@ -0,0 +1,102 @@
+use super::Candidate;
+
+#[cfg(windows)]
+pub fn detect() -> anyhow::Result<Vec<Candidate>> {
+	use std::{
+		fs,
+		path::{Path, PathBuf}
+	};
+
+	use anyhow::Context;
+	use base64::{engine::general_purpose, Engine};
+	use ini::Ini;
+	use registry::{Data, Hive, Security};
+	use serde_json::Value;
+
+	use super::Source;
+
+	let mut candidates = vec![];
+
+	// Check for EOS manifests
+	if let Ok(hive) = Hive::CurrentUser.open(r#"Software\Epic Games\EOS"#, Security::Read) {
+		match hive.value("ModSdkMetadataDir") {
+			Ok(Data::String(d)) => {
+				if let Ok(entries) = fs::read_dir(d.to_string_lossy()) {
+					for entry in entries
+						.filter_map(|x| x.ok())
+						.filter(|x| x.file_type().ok().map(|x| x.is_file()).unwrap_or(false))
+					{
+						if let Ok(manifest_data) =
+							serde_json::from_slice::<Value>(&fs::read(entry.path()).with_context(
+								|| format!("Reading EOS manifest {}", entry.path().display())
+							)?) {
+							if manifest_data
+								.get("AppName")
+								.context("AppName")?
+								.as_str()
+								.context("as_str")? == "Eider"
+							{
+								let game_user_settings_path =
+									Path::new(&std::env::var("LOCALAPPDATA")?)
+										.join("EpicGamesLauncher")
+										.join("Saved")
+										.join("Config")
+										.join("Windows")
+										.join("GameUserSettings.ini");
+
+								let mut username = None;
+
+								if game_user_settings_path.exists() {
+									if let Some(x) = Ini::load_from_file(&game_user_settings_path)
+										.context("Reading GameUserSettings.ini")?
+										.section(Some("Offline"))
+										.and_then(|x| x.get("Data"))
+									{
+										if let Ok(x) = general_purpose::STANDARD.decode(x) {
+											if let Ok(x) = serde_json::from_slice::<Value>(&x) {
+												if let Some(x) = x.get(0) {
+													username = Some(
+														x.get("DisplayName")
+															.context("DisplayName")?
+															.as_str()
+															.context("as_str")?
+															.to_owned()
+													);
+												}
+											}
+										}
+									};
+								}
+
+								candidates.push(Candidate {
+									path: PathBuf::from(
+										manifest_data
+											.get("InstallLocation")
+											.context("InstallLocation")?
+											.as_str()
+											.context("as_str")?
+									),
+									username,
+									source: Source::Eos
+								});
+							}
+						}
+					}
+				}
+			}
+
+			Ok(_) => anyhow::bail!("Registry key ModSdkMetadataDir was not string"),
+
+			Err(_) => {}
+		}
+	}
+
+	Ok(candidates)
+}
+
+/// The Epic Games Launcher only exists on Windows; Linux installs made through Legendary or Heroic
+/// are found by the Legendary source instead.
+#[cfg(not(windows))]
+pub fn detect() -> anyhow::Result<Vec<Candidate>> {
+	Ok(vec![])
+}
@@ -0,0 +1,75 @@
+use std::{
+	fs,
+	path::{Path, PathBuf}
+};
+
+use anyhow::Context;
+use serde_json::Value;
+
+use super::{Candidate, Source};
+
+/// Every folder Legendary might keep its configuration in; Heroic bundles its own copy of
+/// Legendary with a separate configuration folder.
+fn config_folders() -> Vec<PathBuf> {
+	let mut folders = vec![];
+
+	if let Ok(x) = std::env::var("LEGENDARY_CONFIG_PATH") {
+		folders.push(PathBuf::from(x));
+	}
+
+	if cfg!(windows) {
+		if let Ok(x) = std::env::var("USERPROFILE") {
+			folders.push(Path::new(&x).join(".config").join("legendary"));
+		}
+	}
+
+	if let Some(x) = dirs::config_dir() {
+		folders.push(x.join("legendary"));
+		folders.push(x.join("heroic").join("legendaryConfig").join("legendary"));
+	}
+
+	folders.dedup();
+	folders
+}
+
+pub fn detect() -> anyhow::Result<Vec<Candidate>> {
+	let mut candidates = vec![];
+
+	for folder in config_folders() {
+		let legendary_installed_path = folder.join("installed.json");
+
+		// Check for a Legendary install
+		if legendary_installed_path.exists() {
+			let legendary_installed_data: Value = serde_json::from_slice(
+				&fs::read(legendary_installed_path).context("Reading legendary installed")?
+			)
+			.context("Legendary installed as JSON")?;
+
+			if let Some(data) = legendary_installed_data.get("Eider") {
+				candidates.push(Candidate {
+					path: PathBuf::from(
+						data.get("install_path")
+							.context("install_path")?
+							.as_str()
+							.context("as_str")?
+					),
+					username: Some(
+						serde_json::from_slice::<Value>(
+							&fs::read(folder.join("user.json"))
+								.context("Reading legendary user")?
+						)
+						.context("Legendary user as JSON")?
+						.get("displayName")
+						.context("displayName")?
+						.as_str()
+						.context("as_str")?
+						.to_owned()
+					),
+					source: Source::Legendary
+				});
+			}
+		}
+	}
+
+	Ok(candidates)
+}
@@ -0,0 +1,277 @@
+use std::{
+	fs,
+	path::{Path, PathBuf}
+};
+
+use anyhow::{bail, Context};
+
+use super::{Candidate, Source};
+
+/// Every `.GamingRoot` file starts with these four bytes.
+const GAMING_ROOT_MAGIC: &[u8; 4] = b"RGBX";
+
+/// Magic followed by a little-endian u32 holding the number of library folders.
+const GAMING_ROOT_HEADER_LEN: usize = 8;
+
+/// The package identity of HITMAN 3 on the Microsoft Store.
+pub const PACKAGE_NAME: &str = "IOInteractiveAS.PC-HITMAN3-BaseGame";
+
+/// Parse the contents of a `.GamingRoot` file into the library folders it lists.
+///
+/// The Xbox app writes one of these to the root of every drive it installs games to. Paths are
+/// usually relative to the drive root (`\XboxGames`), and are returned with the leading separator
+/// stripped so they can be joined onto the drive root.
+pub fn parse_gaming_root(data: &[u8]) -> anyhow::Result<Vec<PathBuf>> {
+	if data.len() < GAMING_ROOT_HEADER_LEN || &data[0..4] != GAMING_ROOT_MAGIC {
+		bail!(".GamingRoot has an invalid header");
+	}
+
+	let count = u32::from_le_bytes(data[4..8].try_into().context("folder count")?) as usize;
+
+	let body = &data[GAMING_ROOT_HEADER_LEN..];
+
+	let chunks = body.chunks_exact(2);
+
+	if !chunks.remainder().is_empty() {
+		bail!(".GamingRoot body is not valid UTF-16");
+	}
+
+	let units = chunks
+		.map(|x| u16::from_le_bytes([x[0], x[1]]))
+		.collect::<Vec<_>>();
+
+	let folders = units
+		.split(|&x| x == 0)
+		.filter(|x| !x.is_empty())
+		.map(String::from_utf16)
+		.collect::<Result<Vec<_>, _>>()
+		.context(".GamingRoot folder as UTF-16")?
+		.into_iter()
+		.map(|x| PathBuf::from(x.trim_start_matches(['\\', '/']).replace('\\', "/")))
+		.take(count)
+		.collect::<Vec<_>>();
+
+	if folders.len() < count {
+		bail!(
+			".GamingRoot lists {} folders but only {} could be read",
+			count,
+			folders.len()
+		);
+	}
+
+	Ok(folders)
+}
+
+/// Read the library folders of a drive from its `.GamingRoot` file, if it has one.
+pub fn read_gaming_root(drive_root: &Path) -> anyhow::Result<Option<Vec<PathBuf>>> {
+	let path = drive_root.join(".GamingRoot");
+
+	if !path.is_file() {
+		return Ok(None);
+	}
+
+	let data = fs::read(&path).with_context(|| format!("Reading {}", path.display()))?;
+
+	Ok(Some(
+		parse_gaming_root(&data)
+			.with_context(|| format!("Parsing {}", path.display()))?
+			.into_iter()
+			.map(|x| {
+				if x.is_absolute() || x.to_string_lossy().contains(':') {
+					x
+				} else {
+					drive_root.join(x)
+				}
+			})
+			.collect()
+	))
+}
+
+/// The roots of every drive letter that currently exists.
+#[cfg(windows)]
+pub fn drive_roots() -> Vec<PathBuf> {
+	('A'..='Z')
+		.map(|x| PathBuf::from(format!("{x}:\\")))
+		.filter(|x| x.is_dir())
+		.collect()
+}
+
+/// The roots of every drive letter that currently exists.
+#[cfg(not(windows))]
+pub fn drive_roots() -> Vec<PathBuf> {
+	vec![]
+}
+
+/// Whether a title folder's `Content` folder holds HITMAN 3, going by either its Microsoft Store
+/// identity or the game executable.
+fn is_hitman_content(content: &Path) -> bool {
+	let identity_matches = ["MicrosoftGame.config", "appxmanifest.xml"]
+		.iter()
+		.filter_map(|x| fs::read_to_string(content.join(x)).ok())
+		.any(|x| x.contains(PACKAGE_NAME));
+
+	identity_matches || content.join("Retail").join("HITMAN3.exe").is_file()
+}
+
+/// Find every HITMAN 3 install in the `XboxGames/<title>/Content` layout on the given drives.
+///
+/// Library folders are taken from each drive's `.GamingRoot`, falling back to the default
+/// `XboxGames` folder for drives without one (or with one that can't be read).
+pub fn find_installs(drive_roots: &[PathBuf]) -> Vec<PathBuf> {
+	let mut installs = vec![];
+
+	for drive_root in drive_roots {
+		let library_folders = match read_gaming_root(drive_root) {
+			Ok(Some(x)) => x,
+			Ok(None) | Err(_) => vec![drive_root.join("XboxGames")]
+		};
+
+		for library_folder in library_folders {
+			if let Ok(entries) = fs::read_dir(&library_folder) {
+				for entry in entries.filter_map(|x| x.ok()) {
+					let content = entry.path().join("Content");
+
+					if content.is_dir()
+						&& is_hitman_content(&content)
+						&& !installs.contains(&content)
+					{
+						installs.push(content);
+					}
+				}
+			}
+		}
+	}
+
+	installs
+}
+
+/// The Xbox gamertag of the signed-in user.
+#[cfg(windows)]
+fn gamertag() -> Option<String> {
+	use registry::{Data, Hive, Security};
+
+	if let Ok(hive) = Hive::CurrentUser.open(r#"Software\Microsoft\XboxLive"#, Security::Read) {
+		if let Ok(Data::String(d)) = hive.value("ModernGamertag") {
+			return Some(d.to_string_lossy());
+		}
+	}
+
+	None
+}
+
+/// The Xbox gamertag of the signed-in user.
+#[cfg(not(windows))]
+fn gamertag() -> Option<String> {
+	None
+}
+
+pub fn detect() -> anyhow::Result<Vec<Candidate>> {
+	// Check for a Microsoft install
+	let installs = find_installs(&drive_roots());
+
+	let username = if installs.is_empty() {
+		None
+	} else {
+		gamertag()
+	};
+
+	Ok(installs
+		.into_iter()
+		.map(|path| Candidate {
+			path,
+			username: username.to_owned(),
+			source: Source::Microsoft
+		})
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_util::temp_folder;
+
+	/// Lists `\XboxGames` and `E:\Games\Xbox`.
+	const VALID: &[u8] = include_bytes!("fixtures/valid.GamingRoot");
+
+	#[test]
+	fn parses_gaming_root() {
+		assert_eq!(
+			parse_gaming_root(VALID).unwrap(),
+			vec![PathBuf::from("XboxGames"), PathBuf::from("E:/Games/Xbox")]
+		);
+	}
+
+	#[test]
+	fn rejects_bad_magic() {
+		let mut data = VALID.to_vec();
+		data[0..4].copy_from_slice(b"XBGR");
+
+		assert!(parse_gaming_root(&data).is_err());
+		assert!(parse_gaming_root(&VALID[..6]).is_err());
+	}
+
+	#[test]
+	fn rejects_odd_length() {
+		let mut data = VALID.to_vec();
+		data.push(0);
+
+		assert!(parse_gaming_root(&data).is_err());
+	}
+
+	#[test]
+	fn rejects_count_larger_than_folders() {
+		let mut data = VALID.to_vec();
+		data[4..8].copy_from_slice(&3u32.to_le_bytes());
+
+		assert!(parse_gaming_root(&data).is_err());
+	}
+
+	#[test]
+	fn finds_installs() {
+		let drive = temp_folder("microsoft-find-installs");
+
+		// Found through the game executable
+		let by_exe = drive.join("Games/HITMAN 3/Content");
+		fs::create_dir_all(by_exe.join("Retail")).unwrap();
+		fs::write(by_exe.join("Retail/HITMAN3.exe"), b"").unwrap();
+
+		// Found through the package identity
+		let by_identity = drive.join("Games/HITMAN 3 (2)/Content");
+		fs::create_dir_all(&by_identity).unwrap();
+		fs::write(
+			by_identity.join("MicrosoftGame.config"),
+			format!("<Identity Name=\"{PACKAGE_NAME}\" />")
+		)
+		.unwrap();
+
+		fs::create_dir_all(drive.join("Games/Other Game/Content")).unwrap();
+
+		// Not listed in .GamingRoot, so not looked at
+		fs::create_dir_all(drive.join("XboxGames/HITMAN 3/Content/Retail")).unwrap();
+		fs::write(
+			drive.join("XboxGames/HITMAN 3/Content/Retail/HITMAN3.exe"),
+			b""
+		)
+		.unwrap();
+
+		let mut gaming_root = b"RGBX".to_vec();
+		gaming_root.extend(1u32.to_le_bytes());
+		gaming_root.extend("\\Games\0".encode_utf16().flat_map(|x| x.to_le_bytes()));
+		fs::write(drive.join(".GamingRoot"), gaming_root).unwrap();
+
+		let mut installs = find_installs(&[drive.to_owned()]);
+		installs.sort();
+
+		assert_eq!(installs, vec![by_exe, by_identity]);
+
+		// Without a .GamingRoot, the default XboxGames folder is used
+		fs::remove_file(drive.join(".GamingRoot")).unwrap();
+
+		assert_eq!(
+			find_installs(&[drive.to_owned()]),
+			vec![drive.join("XboxGames/HITMAN 3/Content")]
+		);
+
+		fs::remove_dir_all(drive).unwrap();
+	}
+}
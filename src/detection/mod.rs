@@ -0,0 +1,224 @@
+pub mod eos;
+pub mod legendary;
+pub mod microsoft;
+pub mod steam;
+
+use std::{
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		mpsc::{channel, Receiver, Sender},
+		Arc
+	},
+	thread
+};
+
+use crate::{error::InstallerError, game_folder::GameFolder, platform::Platform};
+
+/// Somewhere a game folder can come from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+	Legendary,
+	Eos,
+	Steam,
+	Microsoft,
+	Manual,
+	DriveScan
+}
+
+impl Source {
+	/// The sources checked without any input from the user, in the order they're shown.
+	pub const AUTOMATIC: [Source; 4] = [
+		Source::Legendary,
+		Source::Eos,
+		Source::Steam,
+		Source::Microsoft
+	];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Source::Legendary => "Legendary",
+			Source::Eos => "Epic Games",
+			Source::Steam => "Steam",
+			Source::Microsoft => "Microsoft",
+			Source::Manual => "Manually selected",
+			Source::DriveScan => "Drive search"
+		}
+	}
+
+	/// The platform a folder reported by this source was bought from, if the source only covers
+	/// one.
+	pub fn platform(&self) -> Option<Platform> {
+		match self {
+			Source::Legendary | Source::Eos => Some(Platform::Epic),
+			Source::Steam => Some(Platform::Steam),
+			Source::Microsoft => Some(Platform::Microsoft),
+			Source::Manual | Source::DriveScan => None
+		}
+	}
+
+	fn detect(&self) -> anyhow::Result<Vec<Candidate>> {
+		match self {
+			Source::Legendary => legendary::detect(),
+			Source::Eos => eos::detect(),
+			Source::Steam => steam::detect(),
+			Source::Microsoft => microsoft::detect(),
+			Source::Manual | Source::DriveScan => Ok(vec![])
+		}
+	}
+}
+
+/// A folder a source thinks HITMAN 3 is installed in. It still has to be validated.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Candidate {
+	pub path: PathBuf,
+
+	/// The store account the folder belongs to, if the source knows it.
+	pub username: Option<String>,
+
+	pub source: Source
+}
+
+#[derive(Clone, Debug)]
+pub enum SourceStatus {
+	Running,
+
+	/// The number of candidates the source reported.
+	Done(usize),
+
+	Failed(InstallerError),
+	Cancelled
+}
+
+enum DetectionEvent {
+	Finished(Source, anyhow::Result<Vec<GameFolder>>)
+}
+
+/// Game folder detection across every source, with each source running on its own thread so
+/// that a slow one doesn't hold up the rest (or the UI).
+pub struct Detection {
+	sender: Sender<DetectionEvent>,
+	receiver: Receiver<DetectionEvent>,
+	cancelled: Arc<AtomicBool>,
+	on_event: Arc<dyn Fn() + Send + Sync>,
+	pub statuses: Vec<(Source, SourceStatus)>
+}
+
+impl Detection {
+	/// Start checking every automatic source; `on_event` is called from a detection thread
+	/// whenever there's something new to [`poll`](Self::poll).
+	pub fn start(on_event: impl Fn() + Send + Sync + 'static) -> Self {
+		let (sender, receiver) = channel();
+
+		let mut detection = Self {
+			sender,
+			receiver,
+			cancelled: Arc::new(AtomicBool::new(false)),
+			on_event: Arc::new(on_event),
+			statuses: vec![]
+		};
+
+		for source in Source::AUTOMATIC {
+			detection.spawn(source, move || source.detect());
+		}
+
+		detection
+	}
+
+	fn spawn(
+		&mut self,
+		source: Source,
+		detect: impl FnOnce() -> anyhow::Result<Vec<Candidate>> + Send + 'static
+	) {
+		if self.cancelled.load(Ordering::Relaxed) {
+			self.cancelled = Arc::new(AtomicBool::new(false));
+		}
+
+		self.statuses.retain(|(x, _)| *x != source);
+		self.statuses.push((source, SourceStatus::Running));
+
+		let sender = self.sender.clone();
+		let cancelled = self.cancelled.clone();
+		let on_event = self.on_event.clone();
+
+		thread::spawn(move || {
+			let _span = tracing::info_span!("detection", source = source.name()).entered();
+
+			let result = detect();
+
+			match &result {
+				Ok(x) => tracing::info!("Found {} candidates", x.len()),
+				Err(e) => tracing::warn!("Failed: {e:#}")
+			}
+
+			let result = result.map(|x| x.into_iter().map(GameFolder::inspect).collect());
+
+			if !cancelled.load(Ordering::Relaxed) {
+				let _ = sender.send(DetectionEvent::Finished(source, result));
+				on_event();
+			}
+		});
+	}
+
+	/// Add a folder the user picked themselves as a candidate.
+	pub fn add_manual(&mut self, folder: PathBuf) {
+		self.spawn(Source::Manual, move || {
+			Ok(vec![Candidate {
+				path: folder,
+				username: None,
+				source: Source::Manual
+			}])
+		});
+	}
+
+	/// Stop waiting for any sources that are still running; their results will be discarded.
+	pub fn cancel(&mut self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+
+		for (_, status) in &mut self.statuses {
+			if matches!(status, SourceStatus::Running) {
+				*status = SourceStatus::Cancelled;
+			}
+		}
+	}
+
+	pub fn is_finished(&self) -> bool {
+		!self
+			.statuses
+			.iter()
+			.any(|(_, x)| matches!(x, SourceStatus::Running))
+	}
+
+	/// Collect the folders reported by sources that finished since the last poll.
+	pub fn poll(&mut self) -> Vec<GameFolder> {
+		let mut candidates = vec![];
+
+		while let Ok(DetectionEvent::Finished(source, result)) = self.receiver.try_recv() {
+			let status = match result {
+				Ok(x) => {
+					let status = SourceStatus::Done(x.len());
+					candidates.extend(x);
+					status
+				}
+
+				Err(e) => SourceStatus::Failed(InstallerError::Detection {
+					source: source.name(),
+					detail: format!("{e:?}")
+				})
+			};
+
+			if let Some((_, x)) = self.statuses.iter_mut().find(|(x, _)| *x == source) {
+				*x = status;
+			}
+		}
+
+		candidates
+	}
+}
+
+impl Drop for Detection {
+	fn drop(&mut self) {
+		self.cancel();
+	}
+}
@@ -0,0 +1,146 @@
+use std::{
+	collections::HashMap,
+	fs,
+	path::{Path, PathBuf}
+};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use super::{Candidate, Source};
+
+/// The Steam app IDs HITMAN 3 can be installed under.
+pub const APP_IDS: [&str; 2] = ["1659040", "1847520"];
+
+#[derive(Deserialize)]
+struct SteamLibraryFolder {
+	path: String,
+	apps: HashMap<String, String>
+}
+
+#[derive(Deserialize)]
+struct SteamUser {
+	#[serde(alias = "PersonaName")]
+	#[serde(alias = "personaname")]
+	persona_name: String,
+
+	#[serde(alias = "MostRecent")]
+	#[serde(alias = "mostrecent")]
+	#[serde(default)]
+	most_recent: bool,
+
+	#[serde(alias = "Timestamp")]
+	#[serde(alias = "timestamp")]
+	#[serde(default)]
+	timestamp: u64
+}
+
+/// The folders Steam might be installed to.
+#[cfg(windows)]
+pub fn steam_roots() -> anyhow::Result<Vec<PathBuf>> {
+	use registry::{Data, Hive, Security};
+
+	if let Ok(hive) = Hive::CurrentUser.open(r#"Software\Valve\Steam"#, Security::Read) {
+		match hive.value("SteamPath") {
+			Ok(Data::String(d)) => Ok(vec![PathBuf::from(d.to_string_lossy())]),
+
+			Ok(_) => anyhow::bail!("Registry key SteamPath was not string"),
+
+			Err(_) => Ok(vec![])
+		}
+	} else {
+		Ok(vec![])
+	}
+}
+
+/// The folders Steam might be installed to.
+#[cfg(not(windows))]
+pub fn steam_roots() -> anyhow::Result<Vec<PathBuf>> {
+	let mut roots = vec![];
+
+	if let Some(home) = dirs::home_dir() {
+		for root in [
+			home.join(".steam").join("steam"),
+			home.join(".local").join("share").join("Steam"),
+			home.join(".var")
+				.join("app")
+				.join("com.valvesoftware.Steam")
+				.join(".local")
+				.join("share")
+				.join("Steam")
+		] {
+			if root.is_dir() {
+				// ~/.steam/steam is usually a symlink to ~/.local/share/Steam
+				let root = fs::canonicalize(&root).unwrap_or(root);
+
+				if !roots.contains(&root) {
+					roots.push(root);
+				}
+			}
+		}
+	}
+
+	Ok(roots)
+}
+
+pub fn detect() -> anyhow::Result<Vec<Candidate>> {
+	let mut candidates = vec![];
+
+	// Check for a Steam install
+	for steam_root in steam_roots()? {
+		if let Ok(s) = fs::read_to_string(
+			if steam_root
+				.join("config")
+				.join("libraryfolders.vdf")
+				.exists()
+			{
+				steam_root.join("config").join("libraryfolders.vdf")
+			} else {
+				steam_root.join("steamapps").join("libraryfolders.vdf")
+			}
+		) {
+			let folders: HashMap<String, SteamLibraryFolder> =
+				keyvalues_serde::from_str(&s).context("VDF parse")?;
+
+			for folder in folders.values() {
+				if APP_IDS.iter().any(|x| folder.apps.contains_key(*x)) {
+					let users: HashMap<String, SteamUser> = keyvalues_serde::from_str(
+						&fs::read_to_string(steam_root.join("config").join("loginusers.vdf"))
+							.context("Reading loginusers.vdf")?
+					)?;
+
+					candidates.push(Candidate {
+						path: Path::new(&folder.path)
+							.join("steamapps")
+							.join("common")
+							.join("HITMAN 3"),
+						username: Some(
+							users
+								.values()
+								.find(|x| x.most_recent)
+								.unwrap_or(
+									users
+										.values()
+										.reduce(
+											|cur, x| {
+												if cur.timestamp < x.timestamp {
+													x
+												} else {
+													cur
+												}
+											}
+										)
+										.context("Steam users was empty")?
+								)
+								.persona_name
+								.to_owned()
+						),
+						source: Source::Steam
+					});
+				}
+			}
+		}
+	}
+
+	Ok(candidates)
+}
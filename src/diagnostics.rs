@@ -0,0 +1,314 @@
+use std::{
+	fs::{self, File},
+	io::Write,
+	path::{Path, PathBuf},
+	time::Duration
+};
+
+use anyhow::Context;
+use sysinfo::{System, SystemExt};
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::{
+	api::{detect_games, DetectOptions},
+	crash::crash_folder,
+	detection::{Source, SourceStatus},
+	error::InstallerError,
+	game_folder::GameFolder,
+	logging::log_folder,
+	progress::CancellationToken,
+	receipt::RECEIPT_FILE
+};
+
+/// The name suggested when saving diagnostics.
+pub const DIAGNOSTICS_FILE_NAME: &str = "smf-installer-diagnostics.zip";
+
+/// How long the command line waits for detection before exporting what it has.
+const DETECTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Everything the installer knows that's useful for helping someone on the forum.
+pub struct Diagnostics<'a> {
+	pub statuses: &'a [(Source, SourceStatus)],
+	pub game_folders: &'a [GameFolder],
+
+	/// Folders the framework is already installed in.
+	pub already_installed: &'a [PathBuf],
+
+	/// Folders that were reported but aren't usable game folders.
+	pub rejected_folders: &'a [GameFolder],
+	pub last_error: Option<&'a InstallerError>,
+
+	/// Replace account names and the user's name and home folder.
+	pub anonymize: bool
+}
+
+impl Diagnostics<'_> {
+	/// Write a zip with the logs, crash reports, detection results, install receipts, a summary of
+	/// the system and the last error.
+	pub fn export(&self, output: &Path) -> anyhow::Result<()> {
+		let anonymizer = Anonymizer::new(self.anonymize, self.game_folders, self.rejected_folders);
+
+		let mut zip = ZipWriter::new(File::create(output).context("Creating diagnostics file")?);
+
+		let mut add = |name: &str, contents: &str| -> anyhow::Result<()> {
+			zip.start_file(name, FileOptions::default())
+				.with_context(|| format!("Adding {name}"))?;
+			zip.write_all(anonymizer.apply(contents).as_bytes())
+				.with_context(|| format!("Writing {name}"))?;
+			Ok(())
+		};
+
+		add("environment.txt", &environment())?;
+		add("detection.txt", &self.detection_report())?;
+
+		if let Some(error) = self.last_error {
+			add(
+				"error.txt",
+				&format!(
+					"{}\n\n{}\n\n{}\n",
+					error.title(),
+					error.explanation(),
+					error.detail()
+				)
+			)?;
+		}
+
+		for (index, folder) in self.installed_folders().iter().enumerate() {
+			if let Ok(receipt) =
+				fs::read_to_string(folder.join("Simple Mod Framework").join(RECEIPT_FILE))
+			{
+				add(&format!("receipts/{index}.json"), &receipt)?;
+			}
+		}
+
+		for (folder, prefix) in [
+			(log_folder(), "logs"),
+			(crash_folder(), "crashes"),
+			(crash_folder().map(|x| x.join("new")), "crashes")
+		] {
+			for path in folder
+				.and_then(|x| fs::read_dir(x).ok())
+				.into_iter()
+				.flatten()
+				.filter_map(|x| x.ok())
+				.map(|x| x.path())
+				.filter(|x| x.is_file())
+			{
+				if let (Ok(contents), Some(name)) = (fs::read_to_string(&path), path.file_name()) {
+					add(&format!("{prefix}/{}", name.to_string_lossy()), &contents)?;
+				}
+			}
+		}
+
+		zip.finish().context("Finishing diagnostics file")?;
+
+		Ok(())
+	}
+
+	/// Every folder that might have an install receipt, whether or not it can be installed to.
+	fn installed_folders(&self) -> Vec<&Path> {
+		let mut folders = vec![];
+
+		for folder in self
+			.game_folders
+			.iter()
+			.map(|x| x.path.as_path())
+			.chain(self.already_installed.iter().map(|x| x.as_path()))
+			.chain(
+				self.rejected_folders
+					.iter()
+					.filter(|x| x.check.framework_already_installed)
+					.map(|x| x.path.as_path())
+			) {
+			if !folders.contains(&folder) {
+				folders.push(folder);
+			}
+		}
+
+		folders
+	}
+
+	fn detection_report(&self) -> String {
+		let mut report = String::from("Sources:\n");
+
+		for (source, status) in self.statuses {
+			report += &format!(
+				"{}: {}\n",
+				source.name(),
+				match status {
+					SourceStatus::Running => "still running".to_owned(),
+					SourceStatus::Done(x) => format!("found {x} folders"),
+					SourceStatus::Failed(e) => format!("failed\n{}", e.detail()),
+					SourceStatus::Cancelled => "cancelled".to_owned()
+				}
+			);
+		}
+
+		report += "\nGame folders:\n";
+
+		for folder in self.game_folders {
+			report += &folder_report(folder);
+		}
+
+		report += "\nRejected folders:\n";
+
+		for folder in self.rejected_folders {
+			report += &folder_report(folder);
+		}
+
+		report
+	}
+}
+
+fn folder_report(folder: &GameFolder) -> String {
+	let mut report = format!("{}\n", folder.path.display());
+
+	for x in &folder.reports {
+		report += &format!(
+			"  Reported by {}{}\n",
+			x.source.name(),
+			x.username
+				.as_ref()
+				.map(|x| format!(" under {x}"))
+				.unwrap_or_default()
+		);
+	}
+
+	if let Some(platform) = folder.platform() {
+		report += &format!(
+			"  Platform: {} ({} confidence: {})\n",
+			platform.platform.name(),
+			platform.confidence.name(),
+			platform.evidence.join(", ")
+		);
+	}
+
+	if let Some(version) = folder.game_version {
+		report += &format!("  Game version: {version}\n");
+	}
+
+	report += &format!(
+		"  Framework installed: {}\n",
+		folder.check.framework_already_installed
+	);
+
+	for problem in folder.check.problems() {
+		report += &format!("  Problem: {}\n", problem.explanation());
+	}
+
+	for artifact in &folder.artifacts {
+		report += &format!(
+			"  Artifact: {} ({})\n",
+			artifact.path.display(),
+			artifact.kind.explanation()
+		);
+	}
+
+	if let Some(preflight) = &folder.preflight {
+		for problem in &preflight.problems {
+			report += &format!("  Preflight: {}\n", problem.explanation());
+		}
+	}
+
+	if let Some(runtime) = &folder.wine_runtime {
+		report += &format!("  Runtime: {}\n", runtime.description());
+	}
+
+	report
+}
+
+/// The system, without anything that identifies the user or the PC.
+fn environment() -> String {
+	let system = System::new();
+
+	format!(
+		"Installer version: {}\nSystem: {} {}\nOS version: {}\nKernel version: {}\nInstaller \
+		 location: {}\n",
+		env!("CARGO_PKG_VERSION"),
+		std::env::consts::OS,
+		std::env::consts::ARCH,
+		system.long_os_version().unwrap_or_default(),
+		system.kernel_version().unwrap_or_default(),
+		std::env::current_exe()
+			.map(|x| x.display().to_string())
+			.unwrap_or_default()
+	)
+}
+
+/// Replaces names that identify the user in text.
+struct Anonymizer {
+	replacements: Vec<(String, &'static str)>
+}
+
+impl Anonymizer {
+	fn new(enabled: bool, game_folders: &[GameFolder], rejected_folders: &[GameFolder]) -> Self {
+		let mut replacements = vec![];
+
+		if enabled {
+			if let Some(home) = dirs::home_dir() {
+				replacements.push((home.to_string_lossy().into_owned(), "~"));
+			}
+
+			for folder in game_folders.iter().chain(rejected_folders) {
+				for report in &folder.reports {
+					if let Some(username) = &report.username {
+						replacements.push((username.to_owned(), "<account>"));
+					}
+				}
+			}
+
+			for variable in ["USERNAME", "USER"] {
+				if let Ok(user) = std::env::var(variable) {
+					replacements.push((user, "<user>"));
+				}
+			}
+		}
+
+		// Paths appear escaped in JSON
+		replacements.extend(
+			replacements
+				.iter()
+				.filter(|(x, _)| x.contains('\\'))
+				.map(|(x, replacement)| (x.replace('\\', "\\\\"), *replacement))
+				.collect::<Vec<_>>()
+		);
+
+		// Very short names would replace parts of unrelated words
+		replacements.retain(|(x, _)| x.len() >= 3);
+
+		// Longest first, so that the home folder goes before the user name inside it
+		replacements.sort_by_key(|(x, _)| std::cmp::Reverse(x.len()));
+
+		Self { replacements }
+	}
+
+	fn apply(&self, text: &str) -> String {
+		self.replacements
+			.iter()
+			.fold(text.to_owned(), |text, (x, replacement)| {
+				text.replace(x, replacement)
+			})
+	}
+}
+
+/// Find the game folders without the UI, then export diagnostics about them.
+pub fn export_diagnostics(output: &Path, anonymize: bool) -> anyhow::Result<()> {
+	let detected = detect_games(
+		&DetectOptions {
+			drive_scan: false,
+			timeout: Some(DETECTION_TIMEOUT)
+		},
+		&mut |_| {},
+		&CancellationToken::new()
+	);
+
+	Diagnostics {
+		statuses: &detected.statuses,
+		game_folders: &detected.games.installable,
+		already_installed: &detected.games.already_installed,
+		rejected_folders: &detected.games.rejected,
+		last_error: None,
+		anonymize
+	}
+	.export(output)
+}
@@ -0,0 +1,270 @@
+use std::{
+	fmt, io,
+	path::{Path, PathBuf}
+};
+
+use crate::preflight::PreflightProblem;
+
+/// Something that stopped the installer, with enough context to explain it to the user.
+///
+/// `detail` is the full technical error chain, for bug reports.
+#[derive(Clone, Debug)]
+pub enum InstallerError {
+	/// A store's records couldn't be read while looking for the game.
+	Detection {
+		source: &'static str,
+		detail: String
+	},
+
+	/// Something couldn't be downloaded.
+	Network { url: String, detail: String },
+
+	/// The game folder or the download isn't in a state the framework can be installed from.
+	Verification { path: PathBuf, detail: String },
+
+	/// There's no framework in the game folder to uninstall or update.
+	NotInstalled { path: PathBuf },
+
+	/// The game folder failed the preflight checks.
+	Preflight {
+		path: PathBuf,
+		problems: Vec<PreflightProblem>
+	},
+
+	/// Programs running from the game folder have files open.
+	InUse { path: PathBuf, processes: String },
+
+	/// The framework couldn't be extracted.
+	Extraction { path: PathBuf, detail: String },
+
+	/// A shortcut couldn't be created or removed.
+	Shortcut { path: PathBuf, detail: String },
+
+	/// The system refused access to a file or folder.
+	Permission { path: PathBuf, detail: String },
+
+	/// Any other failure reading or writing files.
+	FileSystem { path: PathBuf, detail: String },
+
+	/// A bug in the installer.
+	Internal { detail: String },
+
+	/// Whoever started the work asked for it to stop.
+	Cancelled
+}
+
+impl InstallerError {
+	/// Wrap an error from working with files, recognising when it's a permission problem.
+	pub fn file(
+		path: &Path,
+		error: anyhow::Error,
+		otherwise: impl FnOnce(PathBuf, String) -> Self
+	) -> Self {
+		let detail = format!("{error:?}");
+
+		if error.chain().any(|x| {
+			x.downcast_ref::<io::Error>()
+				.map_or(false, |x| x.kind() == io::ErrorKind::PermissionDenied)
+		}) {
+			InstallerError::Permission {
+				path: path.to_owned(),
+				detail
+			}
+		} else {
+			otherwise(path.to_owned(), detail)
+		}
+	}
+
+	pub fn internal(error: anyhow::Error) -> Self {
+		InstallerError::Internal {
+			detail: format!("{error:?}")
+		}
+	}
+
+	/// A short summary, like a title.
+	pub fn title(&self) -> &'static str {
+		match self {
+			InstallerError::Detection { .. } => "Couldn't check for the game",
+			InstallerError::Network { .. } => "Couldn't download",
+			InstallerError::Verification { .. } => "Couldn't verify the game",
+			InstallerError::NotInstalled { .. } => "The framework isn't installed",
+			InstallerError::Preflight { .. } => "The game folder isn't ready",
+			InstallerError::InUse { .. } => "The game is in use",
+			InstallerError::Extraction { .. } => "Couldn't extract the framework",
+			InstallerError::Shortcut { .. } => "Couldn't create a shortcut",
+			InstallerError::Permission { .. } => "Access denied",
+			InstallerError::FileSystem { .. } => "Couldn't access a file",
+			InstallerError::Internal { .. } => "Something unexpected happened",
+			InstallerError::Cancelled => "Cancelled"
+		}
+	}
+
+	/// What went wrong, in plain words.
+	pub fn explanation(&self) -> String {
+		match self {
+			InstallerError::Detection { source, .. } => {
+				format!("{source}'s records of installed games couldn't be read.")
+			}
+
+			InstallerError::Network { url, .. } => format!("{url} couldn't be downloaded."),
+
+			InstallerError::Verification { path, .. } => {
+				format!("{} isn't what the installer expected.", path.display())
+			}
+
+			InstallerError::NotInstalled { path } => {
+				format!("The framework isn't installed in {}.", path.display())
+			}
+
+			InstallerError::Preflight { path, problems } => format!(
+				"{} can't be installed to yet:\n{}",
+				path.display(),
+				problems
+					.iter()
+					.map(|x| format!("• {}", x.explanation()))
+					.collect::<Vec<_>>()
+					.join("\n")
+			),
+
+			InstallerError::InUse { path, processes } => format!(
+				"{processes} is running from {} and has files there open.",
+				path.display()
+			),
+
+			InstallerError::Extraction { path, .. } => {
+				format!("The framework couldn't be extracted to {}.", path.display())
+			}
+
+			InstallerError::Shortcut { path, .. } => {
+				format!("The shortcut at {} couldn't be created.", path.display())
+			}
+
+			InstallerError::Permission { path, .. } => {
+				format!("The installer wasn't allowed to change {}.", path.display())
+			}
+
+			InstallerError::FileSystem { path, .. } => {
+				format!("{} couldn't be read or written.", path.display())
+			}
+
+			InstallerError::Internal { .. } => {
+				"The installer ran into a problem it didn't expect.".to_owned()
+			}
+
+			InstallerError::Cancelled => "The installer was stopped before it finished.".to_owned()
+		}
+	}
+
+	/// What the user can do about it.
+	pub fn suggestion(&self) -> &'static str {
+		match self {
+			InstallerError::Detection { .. } => {
+				"The other stores were still checked; if your game wasn't found, select its folder \
+				 manually."
+			}
+
+			InstallerError::Network { .. } => {
+				"Check your internet connection, and that GitHub isn't blocked on your network, \
+				 then try again."
+			}
+
+			InstallerError::Verification { .. } => {
+				"Verify the game files through your store, then try again."
+			}
+
+			InstallerError::NotInstalled { .. } => {
+				"There's nothing to change there; select the game folder the framework is \
+				 installed in."
+			}
+
+			InstallerError::Preflight { .. } => "Fix the problems above, then try again.",
+
+			InstallerError::InUse { .. } => "Close the game and the Mod Manager, then try again.",
+
+			InstallerError::Extraction { .. } => {
+				"Make sure the drive has free space and isn't being changed by anything else, such \
+				 as your store updating the game, then try again."
+			}
+
+			InstallerError::Shortcut { .. } => {
+				"The framework itself was installed; you can open the Mod Manager from the Simple \
+				 Mod Framework folder in the game folder, or try again without that shortcut."
+			}
+
+			InstallerError::Permission { .. } => {
+				if cfg!(windows) {
+					"This is usually Controlled Folder Access in Windows Security or an antivirus \
+					 blocking the installer; allow it through, then try again."
+				} else {
+					"Make sure your user owns the game folder, then try again."
+				}
+			}
+
+			InstallerError::FileSystem { .. } => {
+				"Make sure the file isn't open in another program, then try again."
+			}
+
+			InstallerError::Internal { .. } => {
+				"Try again; if it keeps happening, report it to Atampy26 on Hitman Forum (not \
+				 Nexus Mods) with the technical details below."
+			}
+
+			InstallerError::Cancelled => {
+				"Anything finished before stopping was kept; try again to finish the rest."
+			}
+		}
+	}
+
+	/// A report to paste into a forum post, with the technical details in a code block.
+	pub fn report(&self) -> String {
+		format!(
+			"**Simple Mod Framework installer error: {}**\n\n{}\n\nInstaller version: {}\nSystem: \
+			 {} {}\n\n```\n{}\n```\n",
+			self.title(),
+			self.explanation(),
+			env!("CARGO_PKG_VERSION"),
+			std::env::consts::OS,
+			std::env::consts::ARCH,
+			self.detail()
+		)
+	}
+
+	/// The technical details, for bug reports.
+	pub fn detail(&self) -> String {
+		match self {
+			InstallerError::Preflight { problems, .. } => format!("{problems:?}"),
+			InstallerError::InUse { processes, .. } => processes.to_owned(),
+			InstallerError::NotInstalled { path } => {
+				format!("No Simple Mod Framework folder in {}", path.display())
+			}
+			InstallerError::Cancelled => "Cancelled".to_owned(),
+
+			InstallerError::Detection { detail, .. }
+			| InstallerError::Network { detail, .. }
+			| InstallerError::Verification { detail, .. }
+			| InstallerError::Extraction { detail, .. }
+			| InstallerError::Shortcut { detail, .. }
+			| InstallerError::Permission { detail, .. }
+			| InstallerError::FileSystem { detail, .. }
+			| InstallerError::Internal { detail } => detail.to_owned()
+		}
+	}
+}
+
+/// Wrap an error from reading or writing a file.
+pub fn file_error(path: &Path) -> impl FnOnce(anyhow::Error) -> InstallerError + '_ {
+	move |error| {
+		InstallerError::file(path, error, |path, detail| InstallerError::FileSystem {
+			path,
+			detail
+		})
+	}
+}
+
+impl fmt::Display for InstallerError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}: {}", self.title(), self.explanation())
+	}
+}
+
+impl std::error::Error for InstallerError {}
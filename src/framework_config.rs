@@ -0,0 +1,213 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use serde_json::{Map, Value};
+
+use crate::{game_folder::runtime_folder, platform::Platform};
+
+/// The values the installer can fill in for the Mod Manager, relative to the framework folder.
+pub fn detected_values(game_folder: &Path, platform: Option<Platform>) -> Map<String, Value> {
+	let mut values = Map::new();
+
+	values.insert(
+		"runtimePath".to_owned(),
+		Value::String(
+			if runtime_folder(game_folder).starts_with(game_folder.join("Retail")) {
+				"../Retail/Runtime"
+			} else {
+				"../Runtime"
+			}
+			.to_owned()
+		)
+	);
+
+	values.insert(
+		"retailPath".to_owned(),
+		Value::String("../Retail".to_owned())
+	);
+
+	if let Some(platform) = platform {
+		values.insert(
+			"platform".to_owned(),
+			Value::String(platform.config_name().to_owned())
+		);
+	}
+
+	values
+}
+
+/// Combine the config shipped with the framework, the user's existing config (if the framework
+/// was installed here before) and the detected values. The user's values always win, and detected
+/// values are only added where the user hasn't set anything.
+pub fn merge_config(
+	shipped: Option<Value>,
+	existing: Option<Value>,
+	detected: Map<String, Value>
+) -> Value {
+	let mut config = match shipped {
+		Some(Value::Object(x)) => x,
+		_ => Map::new()
+	};
+
+	let existing = match existing {
+		Some(Value::Object(x)) => x,
+		_ => Map::new()
+	};
+
+	for (key, value) in detected {
+		if !existing.contains_key(&key) {
+			config.insert(key, value);
+		}
+	}
+
+	for (key, value) in existing {
+		config.insert(key, value);
+	}
+
+	Value::Object(config)
+}
+
+/// Read a config.json from the framework folder, if there is one and it's valid JSON.
+pub fn read_config(framework_folder: &Path) -> Option<Value> {
+	serde_json::from_slice(&fs::read(framework_folder.join("config.json")).ok()?).ok()
+}
+
+/// Write the detected platform and folder layout into the framework's config.json, keeping
+/// anything the user had configured before the framework was extracted.
+///
+/// This is how the Mod Manager learns what the installer found, as config.json is the only thing it
+/// reads on first run; the game folder itself is the framework folder's parent, which the paths are
+/// relative to.
+pub fn seed_config(
+	framework_folder: &Path,
+	previous_config: Option<Value>,
+	game_folder: &Path,
+	platform: Option<Platform>
+) -> anyhow::Result<()> {
+	let config = merge_config(
+		read_config(framework_folder),
+		previous_config,
+		detected_values(game_folder, platform)
+	);
+
+	fs::write(
+		framework_folder.join("config.json"),
+		serde_json::to_vec_pretty(&config).context("Serialising config.json")?
+	)
+	.context("Writing config.json")?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+	use crate::test_util::temp_folder;
+
+	#[test]
+	fn user_values_beat_detected_values_beat_shipped_defaults() {
+		let mut detected = Map::new();
+		detected.insert("platform".to_owned(), json!("epic"));
+		detected.insert("runtimePath".to_owned(), json!("../Runtime"));
+		detected.insert("retailPath".to_owned(), json!("../Retail"));
+
+		let config = merge_config(
+			Some(json!({
+				"platform": "steam",
+				"runtimePath": "..\\Runtime",
+				"retailPath": "..\\Retail",
+				"knownMods": []
+			})),
+			Some(json!({
+				"runtimePath": "D:/Custom/Runtime",
+				"loadOrder": ["a"]
+			})),
+			detected
+		);
+
+		assert_eq!(
+			config,
+			json!({
+				"platform": "epic",
+				"runtimePath": "D:/Custom/Runtime",
+				"retailPath": "../Retail",
+				"knownMods": [],
+				"loadOrder": ["a"]
+			})
+		);
+	}
+
+	#[test]
+	fn detects_runtime_path_for_each_layout() {
+		let root = temp_folder("framework-config-runtime-path");
+
+		// Microsoft installs have Runtime next to Retail; Steam and Epic have it inside
+		for (name, platform, layout, runtime_path) in [
+			("Microsoft", Platform::Microsoft, "Runtime", "../Runtime"),
+			(
+				"Steam",
+				Platform::Steam,
+				"Retail/Runtime",
+				"../Retail/Runtime"
+			),
+			(
+				"Epic",
+				Platform::Epic,
+				"Retail/Runtime",
+				"../Retail/Runtime"
+			)
+		] {
+			let game_folder = root.join(name);
+			let framework_folder = game_folder.join("Simple Mod Framework");
+
+			fs::create_dir_all(game_folder.join(layout)).unwrap();
+			fs::create_dir_all(&framework_folder).unwrap();
+			fs::write(
+				framework_folder.join("config.json"),
+				br#"{ "runtimePath": "..\\Retail\\Runtime", "platform": "steam" }"#
+			)
+			.unwrap();
+
+			seed_config(&framework_folder, None, &game_folder, Some(platform)).unwrap();
+
+			let config = read_config(&framework_folder).unwrap();
+			assert_eq!(config["runtimePath"], json!(runtime_path), "{name}");
+			assert_eq!(config["retailPath"], json!("../Retail"), "{name}");
+			assert_eq!(config["platform"], json!(platform.config_name()), "{name}");
+		}
+
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn keeps_previous_config_when_seeding() {
+		let root = temp_folder("framework-config-previous");
+		let game_folder = root.join("HITMAN 3");
+		let framework_folder = game_folder.join("Simple Mod Framework");
+
+		fs::create_dir_all(game_folder.join("Retail").join("Runtime")).unwrap();
+		fs::create_dir_all(&framework_folder).unwrap();
+		fs::write(
+			framework_folder.join("config.json"),
+			br#"{ "platform": "steam" }"#
+		)
+		.unwrap();
+
+		seed_config(
+			&framework_folder,
+			Some(json!({ "platform": "microsoft", "loadOrder": ["a"] })),
+			&game_folder,
+			Some(Platform::Epic)
+		)
+		.unwrap();
+
+		let config = read_config(&framework_folder).unwrap();
+		assert_eq!(config["platform"], json!("microsoft"));
+		assert_eq!(config["loadOrder"], json!(["a"]));
+		assert_eq!(config["runtimePath"], json!("../Retail/Runtime"));
+
+		fs::remove_dir_all(root).unwrap();
+	}
+}
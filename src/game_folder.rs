@@ -0,0 +1,602 @@
+use std::{
+	fs,
+	path::{Component, Path, PathBuf}
+};
+
+use crate::{
+	conflicts::{scan_game_folder, Artifact},
+	detection::{Candidate, Source},
+	game_version::{read_game_version, GameVersion},
+	platform::{infer_platform, Confidence, Platform, PlatformGuess},
+	preflight::Preflight,
+	wine::WineRuntime
+};
+
+/// The result of checking whether a folder is a HITMAN 3 game folder the framework can be installed
+/// into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GameFolderCheck {
+	pub exists: bool,
+
+	/// Game folder has Retail
+	pub subfolder_retail: bool,
+
+	/// Game folder has Runtime or Retail/Runtime
+	pub subfolder_runtime: bool,
+
+	/// User is not trying to install the framework on the wrong game
+	pub ishitman3: bool,
+
+	/// User is trying to install the framework on HITMAN 2, which has the same layout
+	pub ishitman2: bool,
+
+	pub framework_already_installed: bool
+}
+
+/// A reason a folder can't have the framework installed into it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Problem {
+	FolderMissing,
+	RetailMissing,
+	RuntimeMissing,
+	Hitman2Found,
+	ExecutableMissing,
+	FrameworkAlreadyInstalled
+}
+
+impl Problem {
+	pub fn explanation(&self) -> &'static str {
+		match self {
+			Problem::FolderMissing => "the folder doesn't exist",
+			Problem::RetailMissing => "there's no Retail folder in it",
+			Problem::RuntimeMissing => "there's no Runtime folder in it or in Retail",
+			Problem::Hitman2Found => {
+				"Retail contains HITMAN2.exe instead of HITMAN3.exe; the framework only supports \
+				 HITMAN 3"
+			}
+			Problem::ExecutableMissing => "Retail doesn't contain HITMAN3.exe",
+			Problem::FrameworkAlreadyInstalled => "the framework is already installed in it"
+		}
+	}
+}
+
+impl GameFolderCheck {
+	pub fn new(path: &Path) -> Self {
+		Self {
+			exists: path.is_dir(),
+			subfolder_retail: path.join("Retail").is_dir(),
+			subfolder_runtime: path.join("Runtime").is_dir()
+				|| path.join("Retail").join("Runtime").is_dir(),
+			ishitman3: path.join("Retail").join("HITMAN3.exe").is_file(),
+			ishitman2: path.join("Retail").join("HITMAN2.exe").is_file(),
+			framework_already_installed: path.join("Simple Mod Framework").is_dir()
+		}
+	}
+
+	/// The folder is a HITMAN 3 install, whether or not the framework is already in it.
+	pub fn is_game_folder(&self) -> bool {
+		self.subfolder_retail && self.subfolder_runtime && self.ishitman3
+	}
+
+	/// The framework can be installed into the folder.
+	pub fn is_valid(&self) -> bool {
+		self.is_game_folder() && !self.framework_already_installed
+	}
+
+	/// Every check the folder failed; empty if the folder [is valid](Self::is_valid).
+	pub fn problems(&self) -> Vec<Problem> {
+		if !self.exists {
+			return vec![Problem::FolderMissing];
+		}
+
+		let mut problems = vec![];
+
+		if !self.subfolder_retail {
+			problems.push(Problem::RetailMissing);
+		}
+
+		if !self.subfolder_runtime {
+			problems.push(Problem::RuntimeMissing);
+		}
+
+		if !self.ishitman3 {
+			if self.ishitman2 {
+				problems.push(Problem::Hitman2Found);
+			} else if self.subfolder_retail {
+				problems.push(Problem::ExecutableMissing);
+			}
+		}
+
+		if self.framework_already_installed {
+			problems.push(Problem::FrameworkAlreadyInstalled);
+		}
+
+		problems
+	}
+}
+
+/// The game's Runtime folder, which is either in the game folder itself or in Retail.
+pub fn runtime_folder(game_folder: &Path) -> PathBuf {
+	if game_folder.join("Runtime").is_dir() {
+		game_folder.join("Runtime")
+	} else {
+		game_folder.join("Retail").join("Runtime")
+	}
+}
+
+/// A folder the user selected, resolved to the game folder it belongs to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resolution {
+	pub path: PathBuf,
+
+	/// What was changed about the selection, if anything.
+	pub explanation: Option<String>
+}
+
+/// Work out which game folder the user meant when they selected a path that isn't the game folder
+/// itself, such as Retail, Runtime, HITMAN3.exe, the Xbox title folder or a Steam library.
+///
+/// If nothing matches, the selection is returned unchanged so that it can be rejected with the
+/// usual problems.
+pub fn resolve_selection(selected: &Path) -> Resolution {
+	let name = selected
+		.file_name()
+		.map(|x| x.to_string_lossy().to_lowercase())
+		.unwrap_or_default();
+
+	let mut attempts: Vec<(PathBuf, &str)> = vec![];
+
+	if selected.is_file() {
+		if let Some(retail) = selected.parent() {
+			if let Some(game_folder) = retail.parent() {
+				attempts.push((
+					game_folder.to_owned(),
+					"you selected a file in the Retail folder"
+				));
+			}
+		}
+	} else {
+		attempts.push((selected.to_owned(), ""));
+
+		if name == "retail" || name == "runtime" {
+			for ancestor in selected.ancestors().skip(1).take(2) {
+				attempts.push((
+					ancestor.to_owned(),
+					if name == "retail" {
+						"you selected the Retail folder"
+					} else {
+						"you selected the Runtime folder"
+					}
+				));
+			}
+		}
+
+		attempts.push((
+			selected.join("Content"),
+			"Microsoft installs keep the game in the Content folder"
+		));
+
+		for (relative, explanation) in [
+			(
+				Path::new("steamapps").join("common").join("HITMAN 3"),
+				"you selected a Steam library folder"
+			),
+			(
+				Path::new("common").join("HITMAN 3"),
+				"you selected a Steam library's steamapps folder"
+			),
+			(
+				PathBuf::from("HITMAN 3"),
+				"you selected the folder the game is installed in"
+			),
+			(
+				Path::new("HITMAN 3").join("Content"),
+				"you selected the folder the game is installed in"
+			)
+		] {
+			attempts.push((selected.join(relative), explanation));
+		}
+
+		for ancestor in selected.ancestors().skip(1) {
+			attempts.push((
+				ancestor.to_owned(),
+				"you selected a folder inside the game folder"
+			));
+		}
+	}
+
+	for (path, explanation) in attempts {
+		if GameFolderCheck::new(&path).is_game_folder() {
+			return Resolution {
+				explanation: if path == selected {
+					None
+				} else {
+					Some(format!(
+						"Using {} instead of {} because {}.",
+						path.display(),
+						selected.display(),
+						explanation
+					))
+				},
+				path
+			};
+		}
+	}
+
+	Resolution {
+		path: selected.to_owned(),
+		explanation: None
+	}
+}
+
+/// Resolve links, junctions, `.`/`..` and redundant or trailing separators in a path. Paths that
+/// don't exist are only normalised lexically.
+pub fn canonicalize(path: &Path) -> PathBuf {
+	match fs::canonicalize(path) {
+		Ok(canonical) => {
+			// Windows returns verbatim paths (\\?\D:\... or \\?\UNC\server\share\...), which
+			// are confusing to show and don't compare equal to the usual form
+			let canonical = canonical.to_string_lossy().into_owned();
+
+			if let Some(x) = canonical.strip_prefix(r"\\?\UNC\") {
+				PathBuf::from(format!(r"\\{x}"))
+			} else if let Some(x) = canonical.strip_prefix(r"\\?\") {
+				PathBuf::from(x)
+			} else {
+				PathBuf::from(canonical)
+			}
+		}
+
+		Err(_) => {
+			let mut normalised = PathBuf::new();
+
+			for component in path.components() {
+				match component {
+					Component::CurDir => {}
+					Component::ParentDir => {
+						normalised.pop();
+					}
+					x => normalised.push(x)
+				}
+			}
+
+			normalised
+		}
+	}
+}
+
+/// A key that is the same for every spelling of the same folder: canonicalised, with `/`
+/// separators, and lowercase on Windows where paths are case-insensitive.
+pub fn path_key(path: &Path) -> String {
+	lexical_path_key(&canonicalize(path))
+}
+
+/// [`path_key`] without touching the file system, for paths that are already resolved, like the
+/// executables of running processes.
+pub fn lexical_path_key(path: &Path) -> String {
+	let key = path
+		.components()
+		.map(|x| {
+			x.as_os_str()
+				.to_string_lossy()
+				.trim_end_matches(['\\', '/'])
+				.to_owned()
+		})
+		.collect::<Vec<_>>()
+		.join("/");
+
+	if cfg!(windows) {
+		key.to_lowercase()
+	} else {
+		key
+	}
+}
+
+/// A source reporting a folder, and the account it reported it under.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Report {
+	pub source: Source,
+	pub username: Option<String>
+}
+
+/// A folder reported by one or more sources, merged by its canonical path.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct GameFolder {
+	pub path: PathBuf,
+	pub key: String,
+	pub reports: Vec<Report>,
+	pub check: GameFolderCheck,
+
+	/// The version of HITMAN3.exe, if it could be read.
+	pub game_version: Option<GameVersion>,
+
+	/// Files left behind by other mod tools.
+	pub artifacts: Vec<Artifact>,
+
+	/// The platform going by the folder's contents.
+	pub inferred_platform: Option<PlatformGuess>,
+
+	/// The platform the user chose, which beats anything detected.
+	pub platform_override: Option<Platform>,
+
+	/// Checked when the folder is first selected, as it involves writing to the folder.
+	pub preflight: Option<Preflight>,
+
+	/// What the Mod Manager will be run with outside Windows.
+	pub wine_runtime: Option<WineRuntime>
+}
+
+impl GameFolder {
+	/// Look at everything about a candidate's folder. This reads the game executable and walks
+	/// the folder, so it's done on the thread that found the candidate rather than the UI thread.
+	pub fn inspect(candidate: Candidate) -> Self {
+		GameFolder {
+			check: GameFolderCheck::new(&candidate.path),
+			game_version: read_game_version(&candidate.path).ok(),
+			artifacts: scan_game_folder(&candidate.path),
+			inferred_platform: infer_platform(&candidate.path),
+			platform_override: None,
+			preflight: None,
+			wine_runtime: if cfg!(windows) {
+				None
+			} else {
+				Some(WineRuntime::detect(&candidate.path))
+			},
+			key: path_key(&candidate.path),
+			path: canonicalize(&candidate.path),
+			reports: vec![Report {
+				source: candidate.source,
+				username: candidate.username
+			}]
+		}
+	}
+
+	/// The first account any source reported the folder under.
+	pub fn username(&self) -> Option<&str> {
+		self.reports.iter().find_map(|x| x.username.as_deref())
+	}
+
+	/// The names of every source that reported the folder, for display.
+	pub fn source_names(&self) -> String {
+		let mut names: Vec<&str> = vec![];
+
+		for report in &self.reports {
+			if !names.contains(&report.source.name()) {
+				names.push(report.source.name());
+			}
+		}
+
+		names.join(", ")
+	}
+
+	/// The platform the folder most likely came from: the user's choice, then whatever a
+	/// store-specific source reported, then the folder's contents.
+	pub fn platform(&self) -> Option<PlatformGuess> {
+		if let Some(platform) = self.platform_override {
+			return Some(PlatformGuess {
+				platform,
+				confidence: Confidence::High,
+				evidence: vec!["you chose it".to_owned()]
+			});
+		}
+
+		if let Some((source, platform)) = self
+			.reports
+			.iter()
+			.find_map(|x| x.source.platform().map(|platform| (x.source, platform)))
+		{
+			return Some(PlatformGuess {
+				platform,
+				confidence: Confidence::High,
+				evidence: vec![format!("{} reported it", source.name())]
+			});
+		}
+
+		self.inferred_platform.clone()
+	}
+}
+
+/// Add a folder to a list of folders, merging its reports into the folder with the same identity
+/// if there is one. Returns the index of the folder it ended up in.
+pub fn merge_folder(folders: &mut Vec<GameFolder>, folder: GameFolder) -> usize {
+	if let Some(index) = folders.iter().position(|x| x.key == folder.key) {
+		let existing = &mut folders[index];
+
+		for report in folder.reports {
+			if !existing.reports.contains(&report) {
+				existing.reports.push(report);
+			}
+		}
+
+		index
+	} else {
+		folders.push(folder);
+		folders.len() - 1
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_util::temp_folder;
+
+	/// A minimal HITMAN 3 folder in a test folder.
+	fn make_game_folder(path: &Path) {
+		fs::create_dir_all(path.join("Retail")).unwrap();
+		fs::create_dir_all(path.join("Runtime")).unwrap();
+		fs::write(path.join("Retail").join("HITMAN3.exe"), b"").unwrap();
+	}
+
+	#[test]
+	fn resolves_selections_inside_game_folder() {
+		let root = temp_folder("game-folder-resolve-inside");
+		let game_folder = root.join("HITMAN 3");
+		make_game_folder(&game_folder);
+
+		let resolution = resolve_selection(&game_folder);
+		assert_eq!(resolution.path, game_folder);
+		assert_eq!(resolution.explanation, None);
+
+		for selected in [
+			game_folder.join("Retail"),
+			game_folder.join("Runtime"),
+			game_folder.join("Retail").join("HITMAN3.exe")
+		] {
+			let resolution = resolve_selection(&selected);
+			assert_eq!(resolution.path, game_folder, "{}", selected.display());
+			assert!(resolution.explanation.is_some());
+		}
+
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn resolves_retail_runtime() {
+		let root = temp_folder("game-folder-resolve-retail-runtime");
+		let game_folder = root.join("HITMAN 3");
+		fs::create_dir_all(game_folder.join("Retail").join("Runtime")).unwrap();
+		fs::write(game_folder.join("Retail").join("HITMAN3.exe"), b"").unwrap();
+
+		assert_eq!(
+			resolve_selection(&game_folder.join("Retail").join("Runtime")).path,
+			game_folder
+		);
+
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn resolves_steam_library() {
+		let root = temp_folder("game-folder-resolve-steam-library");
+		let game_folder = root.join("steamapps").join("common").join("HITMAN 3");
+		make_game_folder(&game_folder);
+
+		assert_eq!(resolve_selection(&root).path, game_folder);
+		assert_eq!(resolve_selection(&root.join("steamapps")).path, game_folder);
+
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn keys_spellings_of_same_folder_alike() {
+		let root = temp_folder("game-folder-path-key");
+		let game_folder = root.join("HITMAN 3");
+		make_game_folder(&game_folder);
+
+		let key = path_key(&game_folder);
+
+		assert_eq!(
+			path_key(Path::new(&format!("{}/", game_folder.display()))),
+			key
+		);
+		assert_eq!(path_key(&game_folder.join("Retail").join("..")), key);
+		assert_eq!(path_key(&root.join(".").join("HITMAN 3")), key);
+
+		// Paths are only case-insensitive on Windows
+		let upper = root.join("hitman 3");
+
+		if cfg!(windows) {
+			assert_eq!(path_key(&upper), key);
+		} else {
+			assert_ne!(path_key(&upper), key);
+		}
+
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn keys_nonexistent_path_lexically() {
+		let root = temp_folder("game-folder-path-key-missing");
+		let missing = root.join("Missing");
+
+		assert_eq!(
+			path_key(&missing.join("Retail").join("..")),
+			path_key(&missing)
+		);
+		assert_eq!(
+			path_key(Path::new(&format!("{}/", missing.display()))),
+			path_key(&missing)
+		);
+
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn merges_folder_reported_by_two_sources() {
+		let root = temp_folder("game-folder-merge");
+		let game_folder = root.join("HITMAN 3");
+		make_game_folder(&game_folder);
+
+		let mut folders = vec![];
+
+		assert_eq!(
+			merge_folder(
+				&mut folders,
+				GameFolder::inspect(Candidate {
+					path: game_folder.to_owned(),
+					username: None,
+					source: Source::Steam
+				})
+			),
+			0
+		);
+
+		// The same folder spelt differently, from another source, and from the same source again
+		for (path, source) in [
+			(
+				PathBuf::from(format!("{}/", game_folder.display())),
+				Source::Legendary
+			),
+			(game_folder.join("Retail").join(".."), Source::Steam)
+		] {
+			assert_eq!(
+				merge_folder(
+					&mut folders,
+					GameFolder::inspect(Candidate {
+						path,
+						username: None,
+						source
+					})
+				),
+				0
+			);
+		}
+
+		assert_eq!(folders.len(), 1);
+		assert_eq!(folders[0].source_names(), "Steam, Legendary");
+
+		let other = root.join("Other");
+		make_game_folder(&other);
+
+		assert_eq!(
+			merge_folder(
+				&mut folders,
+				GameFolder::inspect(Candidate {
+					path: other,
+					username: None,
+					source: Source::Manual
+				})
+			),
+			1
+		);
+
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn leaves_unrelated_folder_to_be_rejected() {
+		let root = temp_folder("game-folder-resolve-unrelated");
+		let selected = root.join("Documents");
+		fs::create_dir_all(selected.join("Retail")).unwrap();
+
+		let resolution = resolve_selection(&selected);
+		assert_eq!(resolution.path, selected);
+		assert_eq!(resolution.explanation, None);
+		assert!(!GameFolderCheck::new(&resolution.path).is_valid());
+
+		fs::remove_dir_all(root).unwrap();
+	}
+}
@@ -0,0 +1,375 @@
+use std::{
+	fmt,
+	fs::File,
+	io::{Read, Seek, SeekFrom},
+	path::Path
+};
+
+use anyhow::{bail, Context};
+
+/// The resource type ID of version information.
+const RT_VERSION: u32 = 16;
+
+/// The first field of VS_FIXEDFILEINFO.
+const FIXED_FILE_INFO_SIGNATURE: u32 = 0xFEEF04BD;
+
+/// The file version of a game executable, from its PE version resource.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub struct GameVersion {
+	pub major: u16,
+	pub minor: u16,
+	pub patch: u16,
+	pub build: u16
+}
+
+impl fmt::Display for GameVersion {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{}.{}.{}.{}",
+			self.major, self.minor, self.patch, self.build
+		)
+	}
+}
+
+fn u16_at(data: &[u8], offset: usize) -> anyhow::Result<u16> {
+	Ok(u16::from_le_bytes(
+		offset
+			.checked_add(2)
+			.and_then(|end| data.get(offset..end))
+			.context("Unexpected end of PE data")?
+			.try_into()?
+	))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> anyhow::Result<u32> {
+	Ok(u32::from_le_bytes(
+		offset
+			.checked_add(4)
+			.and_then(|end| data.get(offset..end))
+			.context("Unexpected end of PE data")?
+			.try_into()?
+	))
+}
+
+/// Read part of a file, checking it's all there first so that a corrupt size can't allocate more
+/// than the file holds.
+fn read_at(file: &mut File, offset: u64, len: usize) -> anyhow::Result<Vec<u8>> {
+	if offset.saturating_add(len as u64) > file.metadata()?.len() {
+		bail!("Unexpected end of PE file");
+	}
+
+	let mut buf = vec![0; len];
+	file.seek(SeekFrom::Start(offset))?;
+	file.read_exact(&mut buf)?;
+	Ok(buf)
+}
+
+/// A section's virtual address range and where its data is in the file.
+struct Section {
+	virtual_address: u32,
+	virtual_size: u32,
+	raw_size: u32,
+	raw_offset: u32
+}
+
+/// Find the first entry in a resource directory, or the entry with the given ID. Returns the
+/// entry's offset field, relative to the start of the resource section.
+fn resource_entry(rsrc: &[u8], directory: usize, id: Option<u32>) -> anyhow::Result<u32> {
+	let named = u16_at(rsrc, directory + 12)? as usize;
+	let ids = u16_at(rsrc, directory + 14)? as usize;
+
+	for index in 0..named + ids {
+		let entry = directory + 16 + index * 8;
+
+		if id.is_none() || (index >= named && Some(u32_at(rsrc, entry)?) == id) {
+			return u32_at(rsrc, entry + 4);
+		}
+	}
+
+	bail!("Resource directory entry not found")
+}
+
+/// Read the version of a PE executable from its version resource. Only the headers and the
+/// resource section are read, so this is fast even for large executables.
+pub fn read_version(exe: &Path) -> anyhow::Result<GameVersion> {
+	let mut file = File::open(exe).with_context(|| format!("Opening {}", exe.display()))?;
+
+	let dos_header = read_at(&mut file, 0, 64)?;
+
+	if &dos_header[0..2] != b"MZ" {
+		bail!("Not a PE file (no MZ header)");
+	}
+
+	let pe_offset = u32_at(&dos_header, 0x3C)? as u64;
+
+	let pe_header = read_at(&mut file, pe_offset, 24)?;
+
+	if &pe_header[0..4] != b"PE\0\0" {
+		bail!("Not a PE file (no PE signature)");
+	}
+
+	let section_count = u16_at(&pe_header, 6)? as usize;
+	let optional_header_size = u16_at(&pe_header, 20)? as usize;
+
+	let optional_header = read_at(&mut file, pe_offset + 24, optional_header_size)?;
+
+	let data_directories = match u16_at(&optional_header, 0)? {
+		0x10B => 96,
+		0x20B => 112,
+		x => bail!("Unknown optional header magic {x:#x}")
+	};
+
+	// The resource table is the third data directory
+	let resource_rva = u32_at(&optional_header, data_directories + 2 * 8)?;
+
+	if resource_rva == 0 {
+		bail!("Executable has no resources");
+	}
+
+	let section_table = read_at(
+		&mut file,
+		pe_offset + 24 + optional_header_size as u64,
+		section_count * 40
+	)?;
+
+	let sections = (0..section_count)
+		.map(|x| -> anyhow::Result<Section> {
+			Ok(Section {
+				virtual_size: u32_at(&section_table, x * 40 + 8)?,
+				virtual_address: u32_at(&section_table, x * 40 + 12)?,
+				raw_size: u32_at(&section_table, x * 40 + 16)?,
+				raw_offset: u32_at(&section_table, x * 40 + 20)?
+			})
+		})
+		.collect::<anyhow::Result<Vec<_>>>()?;
+
+	let section = sections
+		.iter()
+		.find(|x| {
+			x.virtual_address
+				.checked_add(x.virtual_size.max(x.raw_size))
+				.map_or(false, |end| {
+					resource_rva >= x.virtual_address && resource_rva < end
+				})
+		})
+		.context("No section contains the resource table")?;
+
+	let section_data = read_at(
+		&mut file,
+		section.raw_offset as u64,
+		section.raw_size as usize
+	)?;
+
+	let rsrc = section_data
+		.get((resource_rva - section.virtual_address) as usize..)
+		.context("Resource table is outside its section")?;
+
+	// Type -> name -> language; the high bit of an offset marks a subdirectory
+	let names = resource_entry(rsrc, 0, Some(RT_VERSION)).context("No version resource")?;
+	let languages = resource_entry(rsrc, (names & 0x7FFFFFFF) as usize, None)?;
+	let data_entry = resource_entry(rsrc, (languages & 0x7FFFFFFF) as usize, None)?;
+
+	if data_entry & 0x80000000 != 0 {
+		bail!("Version resource is nested too deeply");
+	}
+
+	let data_rva = u32_at(rsrc, data_entry as usize)?;
+	let data_size = u32_at(rsrc, (data_entry as usize).saturating_add(4))? as usize;
+
+	let version_info_start = data_rva
+		.checked_sub(section.virtual_address)
+		.context("Version resource is outside the resource section")?
+		as usize;
+
+	let version_info = version_info_start
+		.checked_add(data_size)
+		.and_then(|end| section_data.get(version_info_start..end))
+		.context("Version resource is outside the resource section")?;
+
+	// VS_FIXEDFILEINFO follows the VS_VERSION_INFO key, aligned to four bytes
+	let fixed_file_info = (0..version_info.len().saturating_sub(4))
+		.step_by(4)
+		.find(|&x| u32_at(version_info, x).ok() == Some(FIXED_FILE_INFO_SIGNATURE))
+		.context("No fixed file info in version resource")?;
+
+	let version_ms = u32_at(version_info, fixed_file_info + 8)?;
+	let version_ls = u32_at(version_info, fixed_file_info + 12)?;
+
+	Ok(GameVersion {
+		major: (version_ms >> 16) as u16,
+		minor: (version_ms & 0xFFFF) as u16,
+		patch: (version_ls >> 16) as u16,
+		build: (version_ls & 0xFFFF) as u16
+	})
+}
+
+/// Read the version of HITMAN3.exe in a game folder.
+pub fn read_game_version(game_folder: &Path) -> anyhow::Result<GameVersion> {
+	read_version(&game_folder.join("Retail").join("HITMAN3.exe"))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use super::*;
+	use crate::test_util::temp_folder;
+
+	/// Where the resource table's entry in the data directories is.
+	const RESOURCE_DIRECTORY: usize = 88 + 112 + 2 * 8;
+
+	/// Where the only section header is.
+	const SECTION_HEADER: usize = 88 + 240;
+
+	/// Where the resource section's data is in the file, and where it's loaded.
+	const SECTION_DATA: usize = 512;
+	const SECTION_ADDRESS: u32 = 0x1000;
+
+	/// Where the version resource's data entry is, relative to the resource section.
+	const DATA_ENTRY: usize = 72;
+
+	fn put_u16(data: &mut [u8], offset: usize, value: u16) {
+		data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+	}
+
+	fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+		data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+	}
+
+	/// A minimal PE32+ file with a .rsrc section holding a VS_VERSIONINFO for a version.
+	fn fixture(version: GameVersion) -> Vec<u8> {
+		let mut data = vec![0; SECTION_DATA];
+
+		// DOS header, pointing at the PE header straight after it
+		data[0..2].copy_from_slice(b"MZ");
+		put_u32(&mut data, 0x3C, 64);
+
+		// PE header with one section and a PE32+ optional header
+		data[64..68].copy_from_slice(b"PE\0\0");
+		put_u16(&mut data, 64 + 6, 1);
+		put_u16(&mut data, 64 + 20, 240);
+		put_u16(&mut data, 88, 0x20B);
+		put_u32(&mut data, RESOURCE_DIRECTORY, SECTION_ADDRESS);
+
+		let mut rsrc = vec![0; 88];
+
+		// Type, name and language directories, each with one ID entry
+		for (directory, id, offset) in [
+			(0, 16, 0x80000000 | 24),
+			(24, 1, 0x80000000 | 48),
+			(48, 1033, DATA_ENTRY as u32)
+		] {
+			put_u16(&mut rsrc, directory + 14, 1);
+			put_u32(&mut rsrc, directory + 16, id);
+			put_u32(&mut rsrc, directory + 20, offset);
+		}
+
+		// VS_VERSIONINFO: header and key, padded to four bytes, then VS_FIXEDFILEINFO
+		let mut version_info = vec![0; 6];
+		version_info.extend(
+			"VS_VERSION_INFO\0"
+				.encode_utf16()
+				.flat_map(|x| x.to_le_bytes())
+		);
+		version_info.resize(40, 0);
+		version_info.extend(FIXED_FILE_INFO_SIGNATURE.to_le_bytes());
+		version_info.extend(0x10000u32.to_le_bytes());
+		version_info.extend(((version.major as u32) << 16 | version.minor as u32).to_le_bytes());
+		version_info.extend(((version.patch as u32) << 16 | version.build as u32).to_le_bytes());
+		version_info.resize(92, 0);
+
+		put_u32(&mut rsrc, DATA_ENTRY, SECTION_ADDRESS + 88);
+		put_u32(&mut rsrc, DATA_ENTRY + 4, version_info.len() as u32);
+		rsrc.extend(version_info);
+
+		data[SECTION_HEADER..SECTION_HEADER + 5].copy_from_slice(b".rsrc");
+		put_u32(&mut data, SECTION_HEADER + 8, rsrc.len() as u32);
+		put_u32(&mut data, SECTION_HEADER + 12, SECTION_ADDRESS);
+		put_u32(&mut data, SECTION_HEADER + 16, rsrc.len() as u32);
+		put_u32(&mut data, SECTION_HEADER + 20, SECTION_DATA as u32);
+
+		data.extend(rsrc);
+		data
+	}
+
+	const VERSION: GameVersion = GameVersion {
+		major: 3,
+		minor: 170,
+		patch: 1,
+		build: 0
+	};
+
+	/// Write a fixture with some changes made to it and read its version.
+	fn read_fixture(name: &str, change: impl FnOnce(&mut Vec<u8>)) -> anyhow::Result<GameVersion> {
+		let folder = temp_folder(&format!("game-version-{name}"));
+		let exe = folder.join("HITMAN3.exe");
+
+		let mut data = fixture(VERSION);
+		change(&mut data);
+		fs::write(&exe, data).unwrap();
+
+		let result = read_version(&exe);
+
+		fs::remove_dir_all(folder).unwrap();
+
+		result
+	}
+
+	#[test]
+	fn reads_version_resource() {
+		assert_eq!(read_fixture("valid", |_| {}).unwrap(), VERSION);
+	}
+
+	#[test]
+	fn rejects_missing_resource_section() {
+		assert!(read_fixture("no-resources", |data| {
+			put_u32(data, RESOURCE_DIRECTORY, 0);
+		})
+		.is_err());
+
+		assert!(read_fixture("no-rsrc-section", |data| {
+			put_u16(data, 64 + 6, 0);
+		})
+		.is_err());
+	}
+
+	#[test]
+	fn rejects_truncated_file() {
+		for length in [32, 80, 200, SECTION_DATA + 20, SECTION_DATA + 100] {
+			assert!(
+				read_fixture("truncated", |data| data.truncate(length)).is_err(),
+				"{length}"
+			);
+		}
+	}
+
+	#[test]
+	fn rejects_overflowing_offsets() {
+		// A section that wraps past the end of the address space
+		assert!(read_fixture("overflowing-section", |data| {
+			put_u32(data, RESOURCE_DIRECTORY, 0xFFFFFF10);
+			put_u32(data, SECTION_HEADER + 12, 0xFFFFFF00);
+		})
+		.is_err());
+
+		// A section bigger than the file
+		assert!(read_fixture("huge-section", |data| {
+			put_u32(data, SECTION_HEADER + 16, u32::MAX);
+		})
+		.is_err());
+
+		// A version resource bigger than its section
+		assert!(read_fixture("huge-version-info", |data| {
+			put_u32(data, SECTION_DATA + DATA_ENTRY + 4, u32::MAX);
+		})
+		.is_err());
+
+		// A data entry offset at the end of the address space
+		assert!(read_fixture("overflowing-data-entry", |data| {
+			put_u32(data, SECTION_DATA + 48 + 20, 0x7FFFFFFF);
+		})
+		.is_err());
+	}
+}
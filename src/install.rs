@@ -0,0 +1,330 @@
+use std::{
+	fs,
+	io::Cursor,
+	path::{Path, PathBuf}
+};
+
+use anyhow::Context;
+use zip_extract::extract;
+
+use crate::{
+	conflicts::{back_up, scan_game_folder},
+	error::{file_error, InstallerError},
+	framework_config::{read_config, seed_config},
+	game_folder::GameFolder,
+	platform::Platform,
+	preflight::{uncompressed_size, Preflight},
+	processes::ensure_not_running,
+	progress::{CancellationToken, Progress, Step},
+	receipt::{read_receipt, record_baseline, write_receipt, InstallReceipt, RECEIPT_FILE},
+	shortcuts::{system_backend, Shortcut, ShortcutLocation, ShortcutScope},
+	wine::{write_launcher_script, WineRuntime, LAUNCHER_SCRIPT}
+};
+
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct InstallOptions {
+	pub platform: Option<Platform>,
+
+	/// Move files left behind by other mod tools into a backup folder first.
+	pub back_up_conflicts: bool,
+
+	/// The release being installed, for the install receipt.
+	pub framework_version: Option<String>,
+
+	pub start_menu_shortcut: bool,
+	pub desktop_shortcut: bool,
+
+	/// Put shortcuts where every user on the PC sees them rather than just the current user.
+	pub all_users_shortcuts: bool,
+
+	/// What to run the Mod Manager with outside Windows; detected from the game folder if unset.
+	pub wine_runtime: Option<WineRuntime>
+}
+
+impl InstallOptions {
+	/// The options for installing a release to a game folder, going by what was found out about
+	/// the folder, with a Start menu shortcut and nothing else optional.
+	pub fn for_game_folder(folder: &GameFolder, framework_version: Option<String>) -> Self {
+		Self {
+			platform: folder.platform().map(|x| x.platform),
+			framework_version,
+			start_menu_shortcut: true,
+			wine_runtime: folder.wine_runtime.to_owned(),
+			..Default::default()
+		}
+	}
+}
+
+/// The framework's icon, for shortcuts on platforms that can't take it from the executable.
+const ICON: &[u8] = include_bytes!("icon.png");
+
+/// The shortcut to the Mod Manager in a framework folder.
+pub fn mod_manager_shortcut(framework_folder: &Path) -> Shortcut {
+	let mod_manager = framework_folder.join("Mod Manager").join("Mod Manager.exe");
+
+	Shortcut {
+		name: "Simple Mod Framework".to_owned(),
+		description: "Manage and deploy HITMAN 3 mods".to_owned(),
+		// Windows programs can't be run directly elsewhere, so the shortcut runs them through the
+		// launcher script
+		target: if cfg!(windows) {
+			mod_manager.to_owned()
+		} else {
+			framework_folder.join(LAUNCHER_SCRIPT)
+		},
+		arguments: vec![],
+		working_directory: mod_manager.parent().map(|x| x.to_owned()),
+		icon: if cfg!(windows) {
+			None
+		} else {
+			Some(framework_folder.join("icon.png"))
+		}
+	}
+}
+
+/// Extract the framework into a game folder, record what the game looked like, point the
+/// framework's config at the game and add shortcuts to the Mod Manager.
+///
+/// Installing over an existing install keeps the game baseline it recorded, as the game may have
+/// mods deployed by now. Cancelling stops before extracting or before making shortcuts; once the
+/// framework is extracted, its baseline, config and receipt are always written, so that it can be
+/// uninstalled.
+pub fn install(
+	data: &[u8],
+	game_folder: &Path,
+	options: &InstallOptions,
+	progress: &mut dyn FnMut(Progress),
+	cancel: &CancellationToken
+) -> Result<(), InstallerError> {
+	let _span = tracing::info_span!("install", game_folder = %game_folder.display()).entered();
+
+	let framework_folder = game_folder.join("Simple Mod Framework");
+
+	let extraction_error = |error: anyhow::Error| {
+		InstallerError::file(&framework_folder, error, |path, detail| {
+			InstallerError::Extraction { path, detail }
+		})
+	};
+
+	cancel.check()?;
+	progress(Progress::Step(Step::Checking));
+
+	let problems = Preflight::check(game_folder)
+		.problems_for(uncompressed_size(data).map_err(extraction_error)?)
+		.into_iter()
+		.filter(|x| x.is_blocking())
+		.collect::<Vec<_>>();
+
+	if !problems.is_empty() {
+		return Err(InstallerError::Preflight {
+			path: game_folder.to_owned(),
+			problems
+		});
+	}
+
+	ensure_not_running(game_folder)?;
+
+	if options.back_up_conflicts {
+		cancel.check()?;
+		progress(Progress::Step(Step::BackingUp));
+
+		back_up(game_folder, &scan_game_folder(game_folder))
+			.context("Backing up conflicting files")
+			.map_err(file_error(game_folder))?;
+	}
+
+	let previous_config = read_config(&framework_folder);
+
+	let previous_receipt = read_receipt(&framework_folder).unwrap_or_else(|e| {
+		tracing::warn!("Couldn't read the previous install receipt: {e:#}");
+		None
+	});
+
+	cancel.check()?;
+	progress(Progress::Step(Step::Extracting));
+
+	tracing::info_span!("extract", framework_folder = %framework_folder.display()).in_scope(
+		|| {
+			tracing::info!("Extracting {} bytes", data.len());
+
+			extract(Cursor::new(data), &framework_folder, false)
+				.map_err(|x| extraction_error(x.into()))
+		}
+	)?;
+
+	// Recorded before the Mod Manager is first opened, so this is the game as it was before any
+	// mods were deployed
+	let baseline = match &previous_receipt {
+		Some(receipt) => {
+			tracing::info!("Keeping the baseline from the previous install");
+			receipt.baseline.to_owned()
+		}
+
+		None => {
+			progress(Progress::Step(Step::RecordingBaseline));
+
+			record_baseline(game_folder, &framework_folder)
+				.context("Recording game baseline")
+				.map_err(file_error(game_folder))?
+		}
+	};
+
+	progress(Progress::Step(Step::WritingConfig));
+
+	seed_config(
+		&framework_folder,
+		previous_config,
+		game_folder,
+		options.platform
+	)
+	.context("Seeding config.json")
+	.map_err(file_error(&framework_folder.join("config.json")))?;
+
+	if !cfg!(windows) {
+		write_launcher_script(
+			&framework_folder,
+			&options
+				.wine_runtime
+				.to_owned()
+				.unwrap_or_else(|| WineRuntime::detect(game_folder))
+		)
+		.map_err(file_error(&framework_folder.join(LAUNCHER_SCRIPT)))?;
+	}
+
+	// Written before the shortcuts are made, so that an install whose shortcuts fail still has a
+	// receipt, and again once they're made
+	let mut receipt = InstallReceipt {
+		installer_version: env!("CARGO_PKG_VERSION").to_owned(),
+		framework_version: options.framework_version.to_owned(),
+		platform: options.platform.map(|x| x.config_name().to_owned()),
+		// Shortcuts from the previous install that aren't made again are still removed on
+		// uninstall
+		shortcuts: Some(
+			previous_receipt
+				.and_then(|x| x.shortcuts)
+				.unwrap_or_default()
+		),
+		baseline
+	};
+
+	progress(Progress::Step(Step::WritingReceipt));
+
+	write_receipt(&framework_folder, &receipt)
+		.map_err(file_error(&framework_folder.join(RECEIPT_FILE)))?;
+
+	let shortcut = mod_manager_shortcut(&framework_folder);
+
+	if let Some(icon) = &shortcut.icon {
+		fs::write(icon, ICON)
+			.context("Writing icon")
+			.map_err(file_error(icon))?;
+	}
+
+	let backend = system_backend().map_err(InstallerError::internal)?;
+
+	let scope = if options.all_users_shortcuts {
+		ShortcutScope::AllUsers
+	} else {
+		ShortcutScope::CurrentUser
+	};
+
+	let shortcuts = receipt.shortcuts.get_or_insert_with(Vec::new);
+
+	let mut shortcut_error = None;
+
+	cancel.check()?;
+	progress(Progress::Step(Step::CreatingShortcuts));
+
+	let shortcuts_span = tracing::info_span!("shortcuts").entered();
+
+	for (enabled, location) in [
+		(options.start_menu_shortcut, ShortcutLocation::StartMenu),
+		(options.desktop_shortcut, ShortcutLocation::Desktop)
+	] {
+		if enabled {
+			let path = backend
+				.path(&shortcut, location, scope)
+				.unwrap_or_else(|_| PathBuf::from(&shortcut.name));
+
+			match backend.create(&shortcut, location, scope) {
+				Ok(created) => {
+					if !shortcuts.contains(&created) {
+						shortcuts.push(created);
+					}
+
+					tracing::info!("Created {}", path.display());
+				}
+
+				Err(error) => {
+					shortcut_error = Some(InstallerError::file(&path, error, |path, detail| {
+						InstallerError::Shortcut { path, detail }
+					}));
+
+					break;
+				}
+			}
+		}
+	}
+
+	drop(shortcuts_span);
+
+	// Even if a shortcut failed, so that the ones that were made are removed on uninstall
+	write_receipt(&framework_folder, &receipt)
+		.map_err(file_error(&framework_folder.join(RECEIPT_FILE)))?;
+
+	if let Some(error) = shortcut_error {
+		return Err(error);
+	}
+
+	tracing::info!("Installed");
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Write;
+
+	use zip::{write::FileOptions, ZipWriter};
+
+	use super::*;
+	use crate::test_util::temp_folder;
+
+	#[test]
+	fn cancelling_after_extracting_still_writes_receipt() {
+		let game_folder = temp_folder("install-cancel");
+
+		fs::create_dir_all(game_folder.join("Retail").join("Runtime")).unwrap();
+
+		let mut data = Cursor::new(vec![]);
+		let mut zip = ZipWriter::new(&mut data);
+		zip.start_file("Mod Manager/Mod Manager.exe", FileOptions::default())
+			.unwrap();
+		zip.write_all(b"MZ").unwrap();
+		zip.finish().unwrap();
+		drop(zip);
+
+		let cancel = CancellationToken::new();
+
+		let result = install(
+			data.get_ref(),
+			&game_folder,
+			&InstallOptions::default(),
+			&mut |progress| {
+				if let Progress::Step(Step::Extracting) = progress {
+					cancel.cancel();
+				}
+			},
+			&cancel
+		);
+
+		let framework_folder = game_folder.join("Simple Mod Framework");
+
+		assert!(matches!(result, Err(InstallerError::Cancelled)));
+		assert!(framework_folder.join("config.json").is_file());
+		assert!(read_receipt(&framework_folder).unwrap().is_some());
+
+		fs::remove_dir_all(game_folder).unwrap();
+	}
+}
@@ -0,0 +1,53 @@
+use std::{path::Path, process::Command};
+
+use anyhow::Context;
+
+#[cfg(not(windows))]
+use crate::wine::LAUNCHER_SCRIPT;
+
+/// Open the Mod Manager in a framework folder without waiting for it to close.
+///
+/// Nothing is passed to it: the config.json seeded when installing is the handoff, and already
+/// holds the game folder's layout and the platform, so the Mod Manager doesn't ask on first run.
+pub fn launch_mod_manager(framework_folder: &Path) -> anyhow::Result<()> {
+	let mod_manager = framework_folder.join("Mod Manager").join("Mod Manager.exe");
+
+	let mut command = mod_manager_command(framework_folder, &mod_manager);
+
+	command.current_dir(mod_manager.parent().context("Mod Manager parent")?);
+
+	command
+		.spawn()
+		.with_context(|| format!("Launching {}", mod_manager.display()))?;
+
+	Ok(())
+}
+
+/// The installer runs elevated, and Windows won't let files be dragged from Explorer onto an
+/// elevated program, so the Mod Manager is opened through Explorer to run as the signed-in user.
+#[cfg(windows)]
+fn mod_manager_command(_: &Path, mod_manager: &Path) -> Command {
+	let explorer = std::env::var_os("SystemRoot").map_or_else(
+		|| "explorer.exe".into(),
+		|x| std::path::PathBuf::from(x).join("explorer.exe")
+	);
+
+	let mut command = Command::new(explorer);
+	command.arg(mod_manager);
+	command
+}
+
+/// The launcher script runs the Mod Manager in the game's prefix; installs from before there was
+/// one fall back to the system Wine.
+#[cfg(not(windows))]
+fn mod_manager_command(framework_folder: &Path, mod_manager: &Path) -> Command {
+	let launcher = framework_folder.join(LAUNCHER_SCRIPT);
+
+	if launcher.is_file() {
+		Command::new(launcher)
+	} else {
+		let mut command = Command::new("wine");
+		command.arg(mod_manager);
+		command
+	}
+}
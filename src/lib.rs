@@ -1,4 +1,54 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+mod api;
 mod app;
+mod compatibility;
+mod conflicts;
+mod crash;
+mod detection;
+mod diagnostics;
+mod error;
+mod framework_config;
+mod game_folder;
+mod game_version;
+mod install;
+mod launch;
+mod logging;
+mod platform;
+mod preflight;
+mod prerequisites;
+mod processes;
+mod progress;
+mod receipt;
+mod scan;
+mod shortcuts;
+#[cfg(test)]
+mod test_util;
+mod uninstall;
+mod verify;
+mod wine;
+mod wizard;
+pub use api::{
+	check_game_folder, close_processes, detect_games, download_framework, install, latest_release,
+	launch_mod_manager, missing_prerequisites, plan_install, uninstall, update, DetectOptions,
+	DetectedGames, Download, DriveScanStatus, FoundGames, GameSearch, InstallPlan, Prerequisite,
+	ProcessWatch, Release, FRAMEWORK_DOWNLOAD_URL
+};
 pub use app::App;
+pub use compatibility::Compatibility;
+pub use conflicts::{Artifact, ArtifactKind};
+pub use crash::{install_crash_handler, CrashReporter};
+pub use detection::{Candidate, Source, SourceStatus};
+pub use diagnostics::export_diagnostics;
+pub use error::InstallerError;
+pub use game_folder::{GameFolder, GameFolderCheck, Report};
+pub use game_version::GameVersion;
+pub use install::InstallOptions;
+pub use logging::{init_logging, log_folder, Log};
+pub use platform::{Confidence, Platform, PlatformGuess};
+pub use preflight::{Preflight, PreflightProblem};
+pub use processes::RunningProcess;
+pub use progress::{CancellationToken, Progress, Step};
+pub use uninstall::UninstallReport;
+pub use verify::{verify_game, Change, Difference};
+pub use wine::WineRuntime;
@@ -0,0 +1,158 @@
+use std::{
+	collections::VecDeque,
+	fs::{self, File},
+	io::{self, Write},
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex}
+};
+
+use anyhow::Context;
+use tracing::Level;
+
+/// How many of the most recent lines are kept in memory for the log viewer.
+const RECENT_LINES: usize = 500;
+
+/// How many runs' logs are kept; each run starts a new log and the oldest is deleted.
+const KEPT_LOGS: usize = 5;
+
+/// The installer's log, written to a file and kept in memory for the log viewer.
+#[derive(Clone)]
+pub struct Log {
+	inner: Arc<LogInner>
+}
+
+struct LogInner {
+	file: Option<(PathBuf, Mutex<File>)>,
+	recent: Mutex<VecDeque<String>>
+}
+
+impl Log {
+	/// The log file, if it could be created.
+	pub fn path(&self) -> Option<&Path> {
+		self.inner.file.as_ref().map(|(path, _)| path.as_path())
+	}
+
+	pub fn recent_lines(&self) -> Vec<String> {
+		self.inner
+			.recent
+			.lock()
+			.map(|x| x.iter().cloned().collect())
+			.unwrap_or_default()
+	}
+
+	/// The recent lines, or `None` if they're being written right now, for when waiting could
+	/// deadlock, like a panic while logging.
+	pub fn try_recent_lines(&self) -> Option<Vec<String>> {
+		self.inner
+			.recent
+			.try_lock()
+			.ok()
+			.map(|x| x.iter().cloned().collect())
+	}
+}
+
+impl Write for Log {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		if let Some((_, file)) = &self.inner.file {
+			if let Ok(mut file) = file.lock() {
+				file.write_all(buf)?;
+			}
+		}
+
+		// Release builds have no console, so this only shows anything in debug builds
+		if cfg!(debug_assertions) {
+			io::stdout().write_all(buf)?;
+		}
+
+		if let Ok(mut recent) = self.inner.recent.lock() {
+			for line in String::from_utf8_lossy(buf).lines() {
+				if recent.len() == RECENT_LINES {
+					recent.pop_front();
+				}
+
+				recent.push_back(line.to_owned());
+			}
+		}
+
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		if let Some((_, file)) = &self.inner.file {
+			if let Ok(mut file) = file.lock() {
+				file.flush()?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Where logs (and crash reports) are kept.
+pub fn log_folder() -> Option<PathBuf> {
+	dirs::data_local_dir().map(|x| x.join("Simple Mod Framework Installer").join("logs"))
+}
+
+/// Start logging to a new file in the log folder, moving the previous runs' logs along.
+///
+/// Logging still goes to the log viewer if the file can't be created.
+pub fn init_logging() -> Log {
+	let (file, file_error) = match log_folder()
+		.context("No data folder")
+		.and_then(|x| open_log_file(&x))
+	{
+		Ok((path, file)) => (Some((path, Mutex::new(file))), None),
+		Err(e) => (None, Some(e))
+	};
+
+	let log = Log {
+		inner: Arc::new(LogInner {
+			file,
+			recent: Mutex::new(VecDeque::new())
+		})
+	};
+
+	let writer = log.to_owned();
+
+	tracing_subscriber::fmt()
+		.with_max_level(Level::INFO)
+		.with_ansi(false)
+		.with_writer(move || writer.to_owned())
+		.init();
+
+	tracing::info!(
+		version = env!("CARGO_PKG_VERSION"),
+		os = std::env::consts::OS,
+		"Installer started"
+	);
+
+	if let Some(e) = file_error {
+		tracing::warn!("Couldn't create log file: {e:#}");
+	}
+
+	log
+}
+
+/// installer.log is the current run; installer.1.log the one before, and so on.
+fn open_log_file(folder: &Path) -> anyhow::Result<(PathBuf, File)> {
+	fs::create_dir_all(folder).context("Creating log folder")?;
+
+	let name = |x: usize| {
+		if x == 0 {
+			folder.join("installer.log")
+		} else {
+			folder.join(format!("installer.{x}.log"))
+		}
+	};
+
+	for x in (1..KEPT_LOGS).rev() {
+		if name(x - 1).is_file() {
+			fs::rename(name(x - 1), name(x)).context("Rotating logs")?;
+		}
+	}
+
+	let path = name(0);
+	let file = File::create(&path).context("Creating log file")?;
+
+	Ok((path, file))
+}
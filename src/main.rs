@@ -1,21 +1,58 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::{path::PathBuf, process::exit};
+
 use egui::Vec2;
+use simple_mod_framework_installer::{
+	CancellationToken, Compatibility, InstallOptions, InstallerError, Progress
+};
 
-// extern "system" {
-//     fn SetStdHandle(nStdHandle: u32, hHandle: *mut ()) -> i32;
-// }
+#[cfg(windows)]
+extern "system" {
+	fn AttachConsole(process_id: u32) -> i32;
+}
 
 fn main() {
-	// use std::os::windows::io::AsRawHandle;
-	// let error_log = r"C:\Users\User\Documents\Github\simple-mod-framework-installer\target\release\error.txt";
-	// let f = std::fs::File::create(error_log).unwrap();
-	// unsafe {
-	//     SetStdHandle((-12_i32) as u32, f.as_raw_handle().cast())
-	// };
+	let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+	// Before the command line is handled, so that installing from it is logged and crash reports
+	// are written for it too
+	let log = simple_mod_framework_installer::init_logging();
+	let crash_reporter = simple_mod_framework_installer::install_crash_handler(log.to_owned());
+
+	if let Some(command) = args.first() {
+		tracing::info!("Running {command} from the command line");
+
+		// Release builds have no console of their own, so borrow the one we were started from
+		#[cfg(windows)]
+		unsafe {
+			AttachConsole(u32::MAX);
+		}
+
+		match (command.as_str(), args.get(1)) {
+			("verify-game", Some(game_folder)) => exit(verify_game(PathBuf::from(game_folder))),
 
-	tracing_subscriber::fmt::init();
+			("export-diagnostics", Some(output)) => exit(export_diagnostics(
+				PathBuf::from(output),
+				args.iter().any(|x| x == "--anonymize")
+			)),
+
+			("install", Some(game_folder)) => exit(install(PathBuf::from(game_folder), false)),
+			("update", Some(game_folder)) => exit(install(PathBuf::from(game_folder), true)),
+			("uninstall", Some(game_folder)) => exit(uninstall(PathBuf::from(game_folder))),
+
+			_ => {
+				eprintln!(
+					"Usage:\n  {0} install <game folder>\n  {0} update <game folder>\n  {0} \
+					 uninstall <game folder>\n  {0} verify-game <game folder>\n  {0} \
+					 export-diagnostics <output zip> [--anonymize]",
+					env!("CARGO_PKG_NAME")
+				);
+				exit(2);
+			}
+		}
+	}
 
 	let native_options = eframe::NativeOptions {
 		initial_window_size: Some(Vec2 {
@@ -29,10 +66,176 @@ fn main() {
 	eframe::run_native(
 		"Simple Mod Framework Installer",
 		native_options,
-		Box::new(|_| Box::new(simple_mod_framework_installer::App::new()))
+		Box::new(|_| {
+			Box::new(
+				simple_mod_framework_installer::App::new()
+					.with_log(log)
+					.with_crash_reporter(crash_reporter)
+			)
+		})
 	);
 }
 
+/// Print how a game folder differs from when the framework was installed. Exits with 1 if
+/// anything changed, so this can be scripted.
+fn verify_game(game_folder: PathBuf) -> i32 {
+	match simple_mod_framework_installer::verify_game(&game_folder) {
+		Ok(differences) if differences.is_empty() => {
+			println!("The game files match the install baseline.");
+			0
+		}
+
+		Ok(differences) => {
+			for difference in &differences {
+				println!("{difference}");
+			}
+
+			println!(
+				"{} files differ from the install baseline.",
+				differences.len()
+			);
+			1
+		}
+
+		Err(e) => {
+			eprintln!("Couldn't verify the game: {e:?}");
+			2
+		}
+	}
+}
+
+/// Find the game folders and write a diagnostics bundle about them.
+fn export_diagnostics(output: PathBuf, anonymize: bool) -> i32 {
+	match simple_mod_framework_installer::export_diagnostics(&output, anonymize) {
+		Ok(_) => {
+			println!("Diagnostics saved to {}", output.display());
+			0
+		}
+
+		Err(e) => {
+			eprintln!("Couldn't export diagnostics: {e:?}");
+			2
+		}
+	}
+}
+
+/// Download the latest framework and install it to a game folder (or update the install there),
+/// with a Start menu shortcut.
+fn install(game_folder: PathBuf, update: bool) -> i32 {
+	let cancel = CancellationToken::new();
+	let mut last_percent = None;
+
+	let mut progress = |progress: Progress| match progress {
+		Progress::Downloading {
+			received,
+			total: Some(total)
+		} if total > 0 => {
+			let percent = received * 100 / total / 10 * 10;
+
+			if last_percent != Some(percent) {
+				last_percent = Some(percent);
+				println!("Downloading... {percent}%");
+			}
+		}
+
+		Progress::Step(step) => println!("{}...", step.description()),
+
+		_ => {}
+	};
+
+	let result = (|| -> Result<(), InstallerError> {
+		let folder = simple_mod_framework_installer::check_game_folder(&game_folder, update)?;
+		let download = simple_mod_framework_installer::download_framework(&mut progress, &cancel)?;
+
+		let options = InstallOptions::for_game_folder(&folder, download.version.to_owned());
+
+		let plan = simple_mod_framework_installer::plan_install(&download, &folder.path)?;
+
+		if let Compatibility::Unsupported(game_versions) = &plan.compatibility {
+			eprintln!(
+				"Warning: this release of the framework only supports game versions {}.",
+				game_versions.join(", ")
+			);
+		}
+
+		if let Some(e) = plan.blocker() {
+			return Err(e);
+		}
+
+		if update {
+			simple_mod_framework_installer::update(
+				&download.data,
+				&folder.path,
+				&options,
+				&mut progress,
+				&cancel
+			)
+		} else {
+			simple_mod_framework_installer::install(
+				&download.data,
+				&folder.path,
+				&options,
+				&mut progress,
+				&cancel
+			)
+		}
+	})();
+
+	match result {
+		Ok(_) => {
+			println!("The framework was installed to {}.", game_folder.display());
+			0
+		}
+
+		Err(e) => {
+			print_error(&e);
+			2
+		}
+	}
+}
+
+/// Remove the framework from a game folder. Exits with 1 if anything couldn't be put back.
+fn uninstall(game_folder: PathBuf) -> i32 {
+	match simple_mod_framework_installer::uninstall(
+		&game_folder,
+		&mut |progress| {
+			if let Progress::Step(step) = progress {
+				println!("{}...", step.description());
+			}
+		},
+		&CancellationToken::new()
+	) {
+		Ok(report) => {
+			for path in &report.removed {
+				println!("Removed {}", path.display());
+			}
+
+			for path in &report.restored {
+				println!("Restored {}", path.display());
+			}
+
+			for problem in &report.problems {
+				eprintln!("{problem}");
+			}
+
+			if report.problems.is_empty() {
+				0
+			} else {
+				1
+			}
+		}
+
+		Err(e) => {
+			print_error(&e);
+			2
+		}
+	}
+}
+
+fn print_error(error: &InstallerError) {
+	eprintln!("{error}\n{}\n\n{}", error.suggestion(), error.detail());
+}
+
 fn load_icon(data: &[u8]) -> eframe::IconData {
 	let (icon_rgba, icon_width, icon_height) = {
 		let image = image::load_from_memory(data).unwrap().into_rgba8();
@@ -0,0 +1,163 @@
+use std::{fs, path::Path};
+
+use crate::detection::{microsoft::PACKAGE_NAME, steam::APP_IDS};
+
+/// The store a copy of the game was bought from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Platform {
+	Steam,
+	Epic,
+	Microsoft
+}
+
+impl Platform {
+	pub const ALL: [Platform; 3] = [Platform::Steam, Platform::Epic, Platform::Microsoft];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Platform::Steam => "Steam",
+			Platform::Epic => "Epic Games",
+			Platform::Microsoft => "Microsoft"
+		}
+	}
+
+	/// The value the framework's config.json uses for the platform.
+	pub fn config_name(&self) -> &'static str {
+		match self {
+			Platform::Steam => "steam",
+			Platform::Epic => "epic",
+			Platform::Microsoft => "microsoft"
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+	Low,
+	Medium,
+	High
+}
+
+impl Confidence {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Confidence::Low => "low",
+			Confidence::Medium => "medium",
+			Confidence::High => "high"
+		}
+	}
+}
+
+/// A platform, how sure we are of it, and why.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PlatformGuess {
+	pub platform: Platform,
+	pub confidence: Confidence,
+	pub evidence: Vec<String>
+}
+
+/// A file or folder that only appears in copies of the game from one platform.
+struct Fingerprint {
+	platform: Platform,
+	weight: u32,
+	description: &'static str,
+	matches: fn(&Path) -> bool
+}
+
+const FINGERPRINTS: &[Fingerprint] = &[
+	Fingerprint {
+		platform: Platform::Steam,
+		weight: 3,
+		description: "the Steam library has an app manifest for HITMAN 3",
+		matches: |x| {
+			x.parent()
+				.and_then(|x| x.parent())
+				.map(|steamapps| {
+					APP_IDS
+						.iter()
+						.any(|id| steamapps.join(format!("appmanifest_{id}.acf")).is_file())
+				})
+				.unwrap_or(false)
+		}
+	},
+	Fingerprint {
+		platform: Platform::Steam,
+		weight: 2,
+		description: "Retail contains steam_api64.dll",
+		matches: |x| x.join("Retail").join("steam_api64.dll").is_file()
+	},
+	Fingerprint {
+		platform: Platform::Steam,
+		weight: 1,
+		description: "Retail contains steam_appid.txt",
+		matches: |x| x.join("Retail").join("steam_appid.txt").is_file()
+	},
+	Fingerprint {
+		platform: Platform::Epic,
+		weight: 3,
+		description: "the game folder has an .egstore folder from the Epic Games Launcher",
+		matches: |x| x.join(".egstore").is_dir()
+	},
+	Fingerprint {
+		platform: Platform::Epic,
+		weight: 1,
+		description: "Retail contains the Epic Online Services SDK",
+		matches: |x| x.join("Retail").join("EOSSDK-Win64-Shipping.dll").is_file()
+	},
+	Fingerprint {
+		platform: Platform::Microsoft,
+		weight: 3,
+		description: "the game folder has a Microsoft Store manifest for HITMAN 3",
+		matches: |x| {
+			["MicrosoftGame.config", "appxmanifest.xml"]
+				.iter()
+				.filter_map(|file| fs::read_to_string(x.join(file)).ok())
+				.any(|x| x.contains(PACKAGE_NAME))
+		}
+	},
+	Fingerprint {
+		platform: Platform::Microsoft,
+		weight: 1,
+		description: "the game is in the Xbox app's Content folder layout",
+		matches: |x| {
+			x.file_name().map(|x| x == "Content").unwrap_or(false)
+				&& x.ancestors().any(|x| {
+					x.file_name()
+						.map(|x| x == "XboxGames" || x == "WindowsApps")
+						.unwrap_or(false)
+				})
+		}
+	}
+];
+
+/// Work out which platform a game folder came from by the files each platform leaves in it.
+pub fn infer_platform(game_folder: &Path) -> Option<PlatformGuess> {
+	let mut best: Option<(u32, PlatformGuess)> = None;
+
+	for platform in Platform::ALL {
+		let matched = FINGERPRINTS
+			.iter()
+			.filter(|x| x.platform == platform && (x.matches)(game_folder))
+			.collect::<Vec<_>>();
+
+		let score = matched.iter().map(|x| x.weight).sum::<u32>();
+
+		if score > 0 && best.as_ref().map_or(true, |(x, _)| score > *x) {
+			best = Some((
+				score,
+				PlatformGuess {
+					platform,
+					confidence: match score {
+						1 => Confidence::Low,
+						2 => Confidence::Medium,
+						_ => Confidence::High
+					},
+					evidence: matched.iter().map(|x| x.description.to_owned()).collect()
+				}
+			));
+		}
+	}
+
+	best.map(|(_, x)| x)
+}
@@ -0,0 +1,236 @@
+use std::{
+	fs,
+	io::{self, Cursor},
+	path::{Path, PathBuf}
+};
+
+use anyhow::Context;
+use human_bytes::human_bytes;
+use sysinfo::{DiskExt, System, SystemExt};
+use zip::ZipArchive;
+
+use crate::game_folder::runtime_folder;
+
+/// File systems, as Linux names them, that are mounted over the network.
+const NETWORK_FILE_SYSTEMS: &[&str] = &[
+	"nfs",
+	"nfs4",
+	"cifs",
+	"smb3",
+	"smbfs",
+	"9p",
+	"afs",
+	"fuse.sshfs",
+	"fuse.rclone"
+];
+
+/// The name of the file created to test write access.
+const WRITE_TEST_FILE: &str = ".installer-write-test";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PreflightProblem {
+	NotEnoughSpace { needed: u64, available: u64 },
+	NotWritable { folder: PathBuf, error: String },
+	ReadOnly,
+	NetworkShare,
+	Fat32
+}
+
+impl PreflightProblem {
+	/// What's wrong and how to fix it.
+	pub fn explanation(&self) -> String {
+		match self {
+			PreflightProblem::NotEnoughSpace { needed, available } => format!(
+				"The drive the game is on needs {} free to install the framework, but only has \
+				 {}. Free up some space and check again.",
+				human_bytes(*needed as f64),
+				human_bytes(*available as f64)
+			),
+
+			PreflightProblem::NotWritable { folder, error } => {
+				let advice = if folder.components().any(|x| x.as_os_str() == "WindowsApps") {
+					"Games in the WindowsApps folder can't be modified; use \"Enable mods\" in the \
+					 Xbox app first, which moves the game somewhere it can be."
+				} else if cfg!(windows) {
+					"The installer already runs as administrator, so this is usually Controlled \
+					 Folder Access in Windows Security or an antivirus blocking it; allow the \
+					 installer through and check again."
+				} else {
+					"Make sure your user owns the folder, for example with sudo chown -R $USER on \
+					 the game folder, and check again."
+				};
+
+				format!(
+					"The installer couldn't write to {} ({error}). {advice}",
+					folder.display()
+				)
+			}
+
+			PreflightProblem::ReadOnly => String::from(
+				"The drive the game is on is read-only. Remount it with write access, or move the \
+				 game to another drive through your store, and check again."
+			),
+
+			PreflightProblem::NetworkShare => String::from(
+				"The game is on a network drive. The framework works there, but deploying mods \
+				 will be slow and can fail if the connection drops; moving the game to a local \
+				 drive is recommended."
+			),
+
+			PreflightProblem::Fat32 => String::from(
+				"The drive the game is on is formatted as FAT32, which can't hold files larger \
+				 than 4 GB, so deploying large mods will fail. Moving the game to an NTFS or \
+				 exFAT drive is recommended."
+			)
+		}
+	}
+
+	/// Whether the framework can't be installed at all until this is fixed.
+	pub fn is_blocking(&self) -> bool {
+		!matches!(
+			self,
+			PreflightProblem::NetworkShare | PreflightProblem::Fat32
+		)
+	}
+}
+
+/// What the game folder's volume looks like, checked once per folder.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Preflight {
+	/// Free space on the volume, if the volume could be found.
+	pub available_space: Option<u64>,
+
+	/// Problems with the volume and with write access.
+	pub problems: Vec<PreflightProblem>
+}
+
+impl Preflight {
+	pub fn check(game_folder: &Path) -> Self {
+		let mut problems = vec![];
+
+		let mut system = System::new();
+		system.refresh_disks_list();
+
+		// The volume with the longest mount point containing the folder, so that volumes mounted
+		// inside others are matched correctly
+		let disk = system
+			.disks()
+			.iter()
+			.filter(|x| game_folder.starts_with(x.mount_point()))
+			.max_by_key(|x| x.mount_point().as_os_str().len());
+
+		let file_system = disk
+			.map(|x| String::from_utf8_lossy(x.file_system()).to_lowercase())
+			.unwrap_or_default();
+
+		if is_network_path(game_folder) || NETWORK_FILE_SYSTEMS.contains(&file_system.as_str()) {
+			problems.push(PreflightProblem::NetworkShare);
+		}
+
+		if ["fat32", "vfat", "msdos", "fat"].contains(&file_system.as_str()) {
+			problems.push(PreflightProblem::Fat32);
+		}
+
+		for folder in [game_folder.to_owned(), runtime_folder(game_folder)] {
+			if let Err(e) = test_write_access(&folder) {
+				if is_read_only_error(&e) {
+					problems.push(PreflightProblem::ReadOnly);
+				} else {
+					problems.push(PreflightProblem::NotWritable {
+						folder,
+						error: e.to_string()
+					});
+				}
+
+				// One folder failing is enough to know
+				break;
+			}
+		}
+
+		Self {
+			available_space: disk.map(|x| x.available_space()),
+			problems
+		}
+	}
+
+	/// Every problem, including not having `needed` bytes free.
+	pub fn problems_for(&self, needed: u64) -> Vec<PreflightProblem> {
+		let mut problems = self.problems.to_owned();
+
+		if let Some(available) = self.available_space {
+			if available < needed {
+				problems.insert(0, PreflightProblem::NotEnoughSpace { needed, available });
+			}
+		}
+
+		problems
+	}
+}
+
+/// The total size of everything in a zip archive once extracted, from its central directory.
+pub fn uncompressed_size(archive: &[u8]) -> anyhow::Result<u64> {
+	let mut archive = ZipArchive::new(Cursor::new(archive)).context("Reading archive")?;
+
+	let mut size = 0;
+
+	for index in 0..archive.len() {
+		size += archive
+			.by_index_raw(index)
+			.context("Reading archive entry")?
+			.size();
+	}
+
+	Ok(size)
+}
+
+fn test_write_access(folder: &Path) -> io::Result<()> {
+	let path = folder.join(WRITE_TEST_FILE);
+
+	fs::write(&path, b"")?;
+	fs::remove_file(&path)
+}
+
+fn is_read_only_error(error: &io::Error) -> bool {
+	// ERROR_WRITE_PROTECT on Windows, EROFS elsewhere
+	error.raw_os_error() == Some(if cfg!(windows) { 19 } else { 30 })
+}
+
+#[cfg(windows)]
+fn is_network_path(path: &Path) -> bool {
+	use std::{
+		os::windows::ffi::OsStrExt,
+		path::{Component, Prefix}
+	};
+
+	extern "system" {
+		fn GetDriveTypeW(root_path_name: *const u16) -> u32;
+	}
+
+	// DRIVE_REMOTE
+	const REMOTE: u32 = 4;
+
+	match path.components().next() {
+		Some(Component::Prefix(prefix)) => match prefix.kind() {
+			Prefix::UNC(..) | Prefix::VerbatimUNC(..) => true,
+
+			_ => {
+				let root = Path::new(prefix.as_os_str())
+					.join("\\")
+					.as_os_str()
+					.encode_wide()
+					.chain([0])
+					.collect::<Vec<_>>();
+
+				unsafe { GetDriveTypeW(root.as_ptr()) == REMOTE }
+			}
+		},
+
+		_ => false
+	}
+}
+
+#[cfg(not(windows))]
+fn is_network_path(_: &Path) -> bool {
+	false
+}
@@ -0,0 +1,344 @@
+use std::{
+	env, fs,
+	path::{Path, PathBuf},
+	process::{self, Command},
+	time::{SystemTime, UNIX_EPOCH}
+};
+
+use anyhow::{bail, Context};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegistryHive {
+	LocalMachine,
+	CurrentUser
+}
+
+/// Where prerequisite checks look for evidence of what's installed, so that they can be pointed at
+/// something other than the real system.
+pub trait SystemProbe {
+	/// A registry value as a string; numbers are formatted in decimal.
+	fn registry_value(&self, hive: RegistryHive, key: &str, value: &str) -> Option<String>;
+
+	fn file_exists(&self, path: &Path) -> bool;
+
+	/// The System32 folder.
+	fn system_folder(&self) -> Option<PathBuf>;
+}
+
+/// The real system.
+pub struct NativeProbe;
+
+impl SystemProbe for NativeProbe {
+	#[cfg(windows)]
+	fn registry_value(&self, hive: RegistryHive, key: &str, value: &str) -> Option<String> {
+		use registry::{Data, Hive, Security};
+
+		let hive = match hive {
+			RegistryHive::LocalMachine => Hive::LocalMachine,
+			RegistryHive::CurrentUser => Hive::CurrentUser
+		};
+
+		match hive.open(key, Security::Read).ok()?.value(value).ok()? {
+			Data::String(x) => Some(x.to_string_lossy()),
+			Data::U32(x) => Some(x.to_string()),
+			_ => None
+		}
+	}
+
+	#[cfg(not(windows))]
+	fn registry_value(&self, _: RegistryHive, _: &str, _: &str) -> Option<String> {
+		None
+	}
+
+	fn file_exists(&self, path: &Path) -> bool {
+		path.is_file()
+	}
+
+	fn system_folder(&self) -> Option<PathBuf> {
+		env::var_os("SystemRoot").map(|x| PathBuf::from(x).join("System32"))
+	}
+}
+
+/// A runtime the Mod Manager needs that isn't part of Windows.
+pub struct Prerequisite {
+	pub name: &'static str,
+
+	/// What goes wrong without it.
+	pub reason: &'static str,
+
+	is_installed: fn(&dyn SystemProbe) -> bool,
+
+	/// The file name of the redistributable, which can be put next to the installer to avoid
+	/// downloading it.
+	installer_name: &'static str,
+	download_url: &'static str,
+	install_args: &'static [&'static str],
+
+	/// Exit codes that mean the runtime is now installed.
+	success_codes: &'static [i32]
+}
+
+/// The subject of the certificate both redistributables are signed with must include this.
+const PUBLISHER: &str = "O=Microsoft Corporation";
+
+/// The WebView2 runtime's client ID in EdgeUpdate.
+const WEBVIEW2_CLIENT: &str = "{F3017226-FE2A-4295-8BDF-00C3A9A7E4C5}";
+
+pub const PREREQUISITES: &[Prerequisite] = &[
+	Prerequisite {
+		name: "Microsoft Edge WebView2 Runtime",
+		reason: "the Mod Manager's window is a web page shown through WebView2, so it won't open \
+		         without it",
+		is_installed: |probe| {
+			[
+				(
+					RegistryHive::LocalMachine,
+					format!(r"SOFTWARE\WOW6432Node\Microsoft\EdgeUpdate\Clients\{WEBVIEW2_CLIENT}")
+				),
+				(
+					RegistryHive::LocalMachine,
+					format!(r"SOFTWARE\Microsoft\EdgeUpdate\Clients\{WEBVIEW2_CLIENT}")
+				),
+				(
+					RegistryHive::CurrentUser,
+					format!(r"Software\Microsoft\EdgeUpdate\Clients\{WEBVIEW2_CLIENT}")
+				)
+			]
+			.iter()
+			.filter_map(|(hive, key)| probe.registry_value(*hive, key, "pv"))
+			// Uninstalling leaves the key behind with a zero version
+			.any(|x| !x.is_empty() && x != "0.0.0.0")
+		},
+		installer_name: "MicrosoftEdgeWebview2Setup.exe",
+		download_url: "https://go.microsoft.com/fwlink/p/?LinkId=2124703",
+		install_args: &["/silent", "/install"],
+		success_codes: &[0]
+	},
+	Prerequisite {
+		name: "Microsoft Visual C++ Redistributable (x64)",
+		reason: "the tools the framework uses to deploy mods are built against it, so deploying \
+		         will fail without it",
+		is_installed: |probe| {
+			[
+				r"SOFTWARE\Microsoft\VisualStudio\14.0\VC\Runtimes\x64",
+				r"SOFTWARE\WOW6432Node\Microsoft\VisualStudio\14.0\VC\Runtimes\x64"
+			]
+			.iter()
+			.any(|key| {
+				probe.registry_value(RegistryHive::LocalMachine, key, "Installed")
+					== Some("1".to_owned())
+			}) || probe
+				.system_folder()
+				.map_or(false, |x| probe.file_exists(&x.join("vcruntime140_1.dll")))
+		},
+		installer_name: "vc_redist.x64.exe",
+		download_url: "https://aka.ms/vs/17/release/vc_redist.x64.exe",
+		install_args: &["/install", "/quiet", "/norestart"],
+		// 3010 means a restart is needed to finish, 1638 that a newer version is installed
+		success_codes: &[0, 3010, 1638]
+	}
+];
+
+/// The prerequisites that aren't installed.
+pub fn missing_prerequisites(probe: &dyn SystemProbe) -> Vec<&'static Prerequisite> {
+	PREREQUISITES
+		.iter()
+		.filter(|x| !(x.is_installed)(probe))
+		.collect()
+}
+
+impl Prerequisite {
+	/// The redistributable, if it's been put next to the installer.
+	pub fn bundled_installer(&self) -> Option<PathBuf> {
+		env::current_exe()
+			.ok()?
+			.parent()
+			.map(|x| x.join(self.installer_name))
+			.filter(|x| x.is_file())
+	}
+
+	/// Run the bundled redistributable, or download it first if there isn't one, and wait for it to
+	/// finish.
+	///
+	/// The installer runs elevated, so it's copied into a new folder of its own first and only run
+	/// if it's signed by Microsoft.
+	pub fn install(&self) -> anyhow::Result<()> {
+		let folder = unique_temp_folder()?;
+
+		let result = self.install_from(&folder);
+
+		let _ = fs::remove_dir_all(&folder);
+
+		result
+	}
+
+	fn install_from(&self, folder: &Path) -> anyhow::Result<()> {
+		let installer = folder.join(self.installer_name);
+
+		match self.bundled_installer() {
+			Some(bundled) => {
+				fs::copy(&bundled, &installer)
+					.with_context(|| format!("Copying {}", bundled.display()))?;
+			}
+
+			None => {
+				let data = reqwest::blocking::get(self.download_url)
+					.and_then(|x| x.error_for_status())
+					.and_then(|x| x.bytes())
+					.with_context(|| format!("Downloading {}", self.name))?;
+
+				fs::write(&installer, data)
+					.with_context(|| format!("Saving {}", installer.display()))?;
+			}
+		}
+
+		verify_signature(&installer)
+			.with_context(|| format!("Checking the signature of {}", self.installer_name))?;
+
+		let status = Command::new(&installer)
+			.args(self.install_args)
+			.status()
+			.with_context(|| format!("Running {}", installer.display()))?;
+
+		match status.code() {
+			Some(code) if self.success_codes.contains(&code) => Ok(()),
+			Some(code) => bail!("The {} installer failed with exit code {code}", self.name),
+			None => bail!("The {} installer was stopped", self.name)
+		}
+	}
+}
+
+/// A new, empty folder in the temp folder.
+fn unique_temp_folder() -> anyhow::Result<PathBuf> {
+	let nanos = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_nanos();
+
+	let folder = env::temp_dir().join(format!("smf-prerequisite-{}-{nanos}", process::id()));
+
+	// Not create_dir_all, so that a folder someone else made in advance is an error
+	fs::create_dir(&folder).with_context(|| format!("Creating {}", folder.display()))?;
+
+	Ok(folder)
+}
+
+/// Fail unless the file has a valid Authenticode signature from Microsoft.
+#[cfg(windows)]
+fn verify_signature(path: &Path) -> anyhow::Result<()> {
+	// By full path, as the folder the installer runs from is searched before PATH
+	let powershell = env::var_os("SystemRoot")
+		.map(PathBuf::from)
+		.context("SystemRoot isn't set")?
+		.join(r"System32\WindowsPowerShell\v1.0\powershell.exe");
+
+	let output = Command::new(&powershell)
+		.args([
+			"-NoProfile",
+			"-NonInteractive",
+			"-Command",
+			"$signature = Get-AuthenticodeSignature -LiteralPath $env:PREREQUISITE_INSTALLER; \
+			 $signature.Status; $signature.SignerCertificate.Subject"
+		])
+		.env("PREREQUISITE_INSTALLER", path)
+		.output()
+		.with_context(|| format!("Running {}", powershell.display()))?;
+
+	let output = String::from_utf8_lossy(&output.stdout);
+	let mut lines = output.lines().map(|x| x.trim());
+
+	let status = lines.next().unwrap_or_default();
+	let subject = lines.next().unwrap_or_default();
+
+	if status != "Valid" {
+		bail!("The signature isn't valid ({status})");
+	}
+
+	if !subject.split(',').any(|x| x.trim() == PUBLISHER) {
+		bail!("It isn't signed by Microsoft but by {subject}");
+	}
+
+	Ok(())
+}
+
+/// Fail unless the file has a valid Authenticode signature from Microsoft.
+#[cfg(not(windows))]
+fn verify_signature(_: &Path) -> anyhow::Result<()> {
+	bail!("Signatures can only be checked on Windows")
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use super::*;
+
+	#[derive(Default)]
+	struct FakeProbe {
+		registry: HashMap<(String, String), String>,
+		files: Vec<PathBuf>
+	}
+
+	impl SystemProbe for FakeProbe {
+		fn registry_value(&self, _: RegistryHive, key: &str, value: &str) -> Option<String> {
+			self.registry
+				.get(&(key.to_owned(), value.to_owned()))
+				.cloned()
+		}
+
+		fn file_exists(&self, path: &Path) -> bool {
+			self.files.iter().any(|x| x == path)
+		}
+
+		fn system_folder(&self) -> Option<PathBuf> {
+			Some(PathBuf::from(r"C:\Windows\System32"))
+		}
+	}
+
+	fn missing_names(probe: &FakeProbe) -> Vec<&'static str> {
+		missing_prerequisites(probe)
+			.into_iter()
+			.map(|x| x.name)
+			.collect()
+	}
+
+	#[test]
+	fn both_missing() {
+		assert_eq!(
+			missing_names(&FakeProbe::default()),
+			vec![PREREQUISITES[0].name, PREREQUISITES[1].name]
+		);
+	}
+
+	#[test]
+	fn webview2_with_zero_version_is_missing() {
+		let probe = FakeProbe {
+			registry: HashMap::from([(
+				(
+					format!(r"SOFTWARE\Microsoft\EdgeUpdate\Clients\{WEBVIEW2_CLIENT}"),
+					"pv".to_owned()
+				),
+				"0.0.0.0".to_owned()
+			)]),
+			files: vec![PathBuf::from(r"C:\Windows\System32").join("vcruntime140_1.dll")]
+		};
+
+		assert_eq!(missing_names(&probe), vec![PREREQUISITES[0].name]);
+	}
+
+	#[test]
+	fn vc_redist_found_through_dll() {
+		let probe = FakeProbe {
+			registry: HashMap::from([(
+				(
+					format!(r"SOFTWARE\Microsoft\EdgeUpdate\Clients\{WEBVIEW2_CLIENT}"),
+					"pv".to_owned()
+				),
+				"120.0.2210.91".to_owned()
+			)]),
+			files: vec![PathBuf::from(r"C:\Windows\System32").join("vcruntime140_1.dll")]
+		};
+
+		assert!(missing_names(&probe).is_empty());
+	}
+}
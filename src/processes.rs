@@ -0,0 +1,282 @@
+use std::{
+	collections::BTreeSet,
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex
+	},
+	thread,
+	time::{Duration, Instant}
+};
+
+use anyhow::bail;
+use sysinfo::{get_current_pid, Pid, PidExt, Process, ProcessExt, System, SystemExt};
+
+use crate::{
+	error::InstallerError,
+	game_folder::{lexical_path_key, path_key}
+};
+
+/// Executables that lock files in the game folder while they're running. Under Wine the process's
+/// executable is Wine itself, so these are matched by name and then placed by their working
+/// folder or command line.
+const GAME_EXECUTABLES: &[&str] = &["HITMAN3.exe", "Mod Manager.exe"];
+
+/// How often [`ProcessWatch`] looks at the process list again.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long processes get to close before giving up on them.
+pub const CLOSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A process running from inside a game folder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RunningProcess {
+	pub pid: u32,
+	pub name: String,
+	pub exe: PathBuf
+}
+
+/// Find processes whose executable is inside the game folder.
+pub fn running_processes(game_folder: &Path) -> Vec<RunningProcess> {
+	// Only the game folder is canonicalised; process paths come from the OS already resolved
+	let folder_key = format!("{}/", path_key(game_folder));
+
+	let current = get_current_pid().ok();
+
+	let mut system = System::new();
+	system.refresh_processes();
+
+	let mut processes = system
+		.processes()
+		.values()
+		.filter(|x| Some(x.pid()) != current)
+		.filter(|x| is_in_folder(x, &folder_key))
+		.map(|x| RunningProcess {
+			pid: x.pid().as_u32(),
+			name: x.name().to_owned(),
+			exe: x.exe().to_owned()
+		})
+		.collect::<Vec<_>>();
+
+	processes.sort_by_key(|x| x.pid);
+	processes
+}
+
+/// Whether a process is running from the folder with the given key (ending in `/`).
+fn is_in_folder(process: &Process, folder_key: &str) -> bool {
+	let in_folder = |path: &Path| format!("{}/", lexical_path_key(path)).starts_with(folder_key);
+
+	if !process.exe().as_os_str().is_empty() && in_folder(process.exe()) {
+		return true;
+	}
+
+	!cfg!(windows)
+		&& GAME_EXECUTABLES.contains(&process.name())
+		&& (in_folder(process.cwd())
+			|| process
+				.cmd()
+				.iter()
+				.filter_map(|x| unix_path(x))
+				.any(|x| in_folder(&x)))
+}
+
+/// A path from a Wine command line as a Linux path; the Linux file system is the Z: drive.
+fn unix_path(arg: &str) -> Option<PathBuf> {
+	if let Some(path) = arg.strip_prefix("Z:").or_else(|| arg.strip_prefix("z:")) {
+		Some(PathBuf::from(path.replace('\\', "/")))
+	} else if arg.starts_with('/') {
+		Some(PathBuf::from(arg))
+	} else {
+		None
+	}
+}
+
+/// The processes' names for showing to the user, like "HITMAN3.exe, Mod Manager.exe".
+pub fn process_names(processes: &[RunningProcess]) -> String {
+	processes
+		.iter()
+		.map(|x| x.name.as_str())
+		.collect::<BTreeSet<_>>()
+		.into_iter()
+		.collect::<Vec<_>>()
+		.join(", ")
+}
+
+/// Ask processes to close as if the user had closed their windows, so that they can save first.
+pub fn request_close(processes: &[RunningProcess]) {
+	for process in processes {
+		close_process(process.pid);
+	}
+}
+
+/// Ask processes to close and wait until they have, giving up after the timeout.
+pub fn close_and_wait(processes: &[RunningProcess], timeout: Duration) -> anyhow::Result<()> {
+	request_close(processes);
+
+	let start = Instant::now();
+
+	let mut system = System::new();
+
+	loop {
+		system.refresh_processes();
+
+		let remaining = processes
+			.iter()
+			.filter(|x| system.process(Pid::from_u32(x.pid)).is_some())
+			.map(|x| x.name.as_str())
+			.collect::<Vec<_>>();
+
+		if remaining.is_empty() {
+			return Ok(());
+		}
+
+		if start.elapsed() > timeout {
+			bail!(
+				"{} didn't close; close it yourself and try again",
+				remaining.join(", ")
+			);
+		}
+
+		thread::sleep(Duration::from_millis(250));
+	}
+}
+
+/// Fail if anything is running from the game folder; checked right before writing to it.
+pub fn ensure_not_running(game_folder: &Path) -> Result<(), InstallerError> {
+	let processes = running_processes(game_folder);
+
+	if !processes.is_empty() {
+		return Err(InstallerError::InUse {
+			path: game_folder.to_owned(),
+			processes: process_names(&processes)
+		});
+	}
+
+	Ok(())
+}
+
+/// The processes running from one game folder, re-checked every few seconds on a thread of its
+/// own, as listing every process can take a while.
+pub struct ProcessWatch {
+	pub game_folder: PathBuf,
+	running: Arc<Mutex<Option<Vec<RunningProcess>>>>,
+	stopped: Arc<AtomicBool>
+}
+
+impl ProcessWatch {
+	/// Start watching; `on_change` is called from the watch thread whenever what's running changes.
+	pub fn start(game_folder: &Path, on_change: impl Fn() + Send + 'static) -> Self {
+		let watch = Self {
+			game_folder: game_folder.to_owned(),
+			running: Arc::new(Mutex::new(None)),
+			stopped: Arc::new(AtomicBool::new(false))
+		};
+
+		let game_folder = watch.game_folder.to_owned();
+		let running = watch.running.clone();
+		let stopped = watch.stopped.clone();
+
+		thread::spawn(move || {
+			while !stopped.load(Ordering::Relaxed) {
+				let processes = running_processes(&game_folder);
+
+				let changed = {
+					let mut running = running.lock().unwrap();
+					let changed = running.as_ref() != Some(&processes);
+					*running = Some(processes);
+					changed
+				};
+
+				if changed {
+					on_change();
+				}
+
+				thread::sleep(WATCH_INTERVAL);
+			}
+		});
+
+		watch
+	}
+
+	/// What was running at the last check, or `None` until the first check has finished.
+	pub fn running(&self) -> Option<Vec<RunningProcess>> {
+		self.running.lock().unwrap().to_owned()
+	}
+}
+
+impl Drop for ProcessWatch {
+	fn drop(&mut self) {
+		self.stopped.store(true, Ordering::Relaxed);
+	}
+}
+
+#[cfg(windows)]
+fn close_process(pid: u32) {
+	type Window = isize;
+
+	#[link(name = "user32")]
+	extern "system" {
+		fn EnumWindows(callback: extern "system" fn(Window, isize) -> i32, param: isize) -> i32;
+		fn GetWindowThreadProcessId(window: Window, process_id: *mut u32) -> u32;
+		fn PostMessageW(window: Window, message: u32, wparam: usize, lparam: isize) -> i32;
+	}
+
+	const WM_CLOSE: u32 = 0x0010;
+
+	extern "system" fn close_window(window: Window, pid: isize) -> i32 {
+		let mut window_pid = 0;
+
+		unsafe {
+			GetWindowThreadProcessId(window, &mut window_pid);
+
+			if window_pid as isize == pid {
+				PostMessageW(window, WM_CLOSE, 0, 0);
+			}
+		}
+
+		// Keep enumerating, as a process can have several top-level windows
+		1
+	}
+
+	unsafe {
+		EnumWindows(close_window, pid as isize);
+	}
+}
+
+#[cfg(not(windows))]
+fn close_process(pid: u32) {
+	use sysinfo::Signal;
+
+	let mut system = System::new();
+	system.refresh_processes();
+
+	if let Some(process) = system.process(Pid::from_u32(pid)) {
+		process.kill_with(Signal::Term);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn process(pid: u32, name: &str) -> RunningProcess {
+		RunningProcess {
+			pid,
+			name: name.to_owned(),
+			exe: PathBuf::from(name)
+		}
+	}
+
+	#[test]
+	fn names_each_program_once() {
+		assert_eq!(
+			process_names(&[
+				process(1, "Mod Manager.exe"),
+				process(2, "HITMAN3.exe"),
+				process(3, "Mod Manager.exe")
+			]),
+			"HITMAN3.exe, Mod Manager.exe"
+		);
+	}
+}
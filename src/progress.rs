@@ -0,0 +1,90 @@
+use std::sync::{
+	atomic::{AtomicBool, Ordering},
+	Arc
+};
+
+use crate::error::InstallerError;
+
+/// Something that happened while the installer was working, for showing progress.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Progress {
+	/// A detection source finished; `found` is how many folders it reported.
+	SourceFinished {
+		source: &'static str,
+		found: usize
+	},
+
+	/// Searching the drives reached a new folder.
+	Searching(std::path::PathBuf),
+
+	Downloading {
+		received: u64,
+		total: Option<u64>
+	},
+
+	Step(Step)
+}
+
+/// A step of installing, updating or uninstalling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+	Checking,
+	BackingUp,
+	Extracting,
+	RecordingBaseline,
+	WritingConfig,
+	WritingReceipt,
+	CreatingShortcuts,
+	RemovingDeployedFiles,
+	RestoringConfig,
+	RemovingShortcuts,
+	RemovingFramework
+}
+
+impl Step {
+	pub fn description(&self) -> &'static str {
+		match self {
+			Step::Checking => "Checking the game folder",
+			Step::BackingUp => "Moving files from other mod tools",
+			Step::Extracting => "Extracting the framework",
+			Step::RecordingBaseline => "Recording the game files",
+			Step::WritingConfig => "Writing the framework's config",
+			Step::WritingReceipt => "Writing the install receipt",
+			Step::CreatingShortcuts => "Creating shortcuts",
+			Step::RemovingDeployedFiles => "Removing deployed mods",
+			Step::RestoringConfig => "Restoring the game's config files",
+			Step::RemovingShortcuts => "Removing shortcuts",
+			Step::RemovingFramework => "Removing the framework"
+		}
+	}
+}
+
+/// Asks long-running work to stop. Work checks it between steps, so anything already written
+/// stays written.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+	cancelled: Arc<AtomicBool>
+}
+
+impl CancellationToken {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::Relaxed)
+	}
+
+	/// Fail with [`InstallerError::Cancelled`] if cancelled.
+	pub fn check(&self) -> Result<(), InstallerError> {
+		if self.is_cancelled() {
+			Err(InstallerError::Cancelled)
+		} else {
+			Ok(())
+		}
+	}
+}
@@ -0,0 +1,190 @@
+use std::{
+	collections::BTreeMap,
+	fs,
+	path::{Path, PathBuf}
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{conflicts::parse_chunk_name, game_folder::runtime_folder};
+
+/// Where the receipt is kept, relative to the framework folder.
+pub const RECEIPT_FILE: &str = "installer-receipt.json";
+
+/// Where copies of the game's own config files are kept, relative to the framework folder.
+pub const VANILLA_BACKUP_FOLDER: &str = "Installer Backup";
+
+/// What the installer did and what the game looked like before the framework touched it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallReceipt {
+	pub installer_version: String,
+	pub framework_version: Option<String>,
+	pub platform: Option<String>,
+
+	/// The shortcuts that were created, to be removed on uninstall; `None` for receipts written
+	/// before shortcuts were recorded.
+	#[serde(default)]
+	pub shortcuts: Option<Vec<PathBuf>>,
+
+	pub baseline: Baseline
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Baseline {
+	/// The names of the rpkg chunks in Runtime.
+	pub chunks: Vec<String>,
+
+	/// Every file in Retail and Runtime.
+	pub files: Vec<FileRecord>,
+
+	/// Files copied to the backup folder, relative to the game folder.
+	pub backed_up_files: Vec<PathBuf>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRecord {
+	/// Relative to the game folder.
+	pub path: PathBuf,
+	pub size: u64,
+
+	/// Only recorded for the config files; the chunks are far too large to hash during install.
+	pub sha256: Option<String>
+}
+
+/// The game's config files the framework edits when deploying, relative to the game folder.
+pub fn config_files(game_folder: &Path) -> Vec<PathBuf> {
+	let runtime = runtime_folder(game_folder);
+
+	vec![
+		runtime
+			.join("packagedefinition.txt")
+			.strip_prefix(game_folder)
+			.map(|x| x.to_owned())
+			.unwrap_or_else(|_| PathBuf::from("Runtime").join("packagedefinition.txt")),
+		PathBuf::from("Retail").join("thumbs.dat"),
+	]
+}
+
+fn sha256(path: &Path) -> anyhow::Result<String> {
+	Ok(hex::encode(Sha256::digest(
+		fs::read(path).with_context(|| format!("Reading {}", path.display()))?
+	)))
+}
+
+/// List the files in a folder and all of its subfolders.
+fn walk(folder: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+	for entry in fs::read_dir(folder).with_context(|| format!("Reading {}", folder.display()))? {
+		let entry = entry?;
+
+		if entry.file_type()?.is_dir() {
+			walk(&entry.path(), files)?;
+		} else {
+			files.push(entry.path());
+		}
+	}
+
+	Ok(())
+}
+
+/// Record the name and size of every file in Retail and Runtime, and the hash of each config
+/// file.
+pub fn record_files(game_folder: &Path) -> anyhow::Result<Vec<FileRecord>> {
+	let config_files = config_files(game_folder);
+
+	let mut paths = vec![];
+	walk(&game_folder.join("Retail"), &mut paths)?;
+
+	// Runtime is usually next to Retail rather than inside it
+	let runtime = runtime_folder(game_folder);
+	if !runtime.starts_with(game_folder.join("Retail")) {
+		walk(&runtime, &mut paths)?;
+	}
+
+	// Sorted by path, so that baselines can be compared and read by people
+	let mut records = BTreeMap::new();
+
+	for path in paths {
+		let relative = path
+			.strip_prefix(game_folder)
+			.context("File outside game folder")?
+			.to_owned();
+
+		records.insert(
+			relative.to_owned(),
+			FileRecord {
+				size: fs::metadata(&path)
+					.with_context(|| format!("Reading metadata of {}", path.display()))?
+					.len(),
+				sha256: if config_files.contains(&relative) {
+					Some(sha256(&path)?)
+				} else {
+					None
+				},
+				path: relative
+			}
+		);
+	}
+
+	Ok(records.into_values().collect())
+}
+
+/// Record the state of the game and copy its config files into the framework folder, so that
+/// they can be restored later.
+pub fn record_baseline(game_folder: &Path, framework_folder: &Path) -> anyhow::Result<Baseline> {
+	let mut chunks = fs::read_dir(runtime_folder(game_folder))
+		.context("Reading Runtime")?
+		.filter_map(|x| x.ok())
+		.map(|x| x.file_name().to_string_lossy().into_owned())
+		.filter(|x| parse_chunk_name(x).is_some())
+		.collect::<Vec<_>>();
+
+	chunks.sort();
+
+	let mut backed_up_files = vec![];
+
+	for file in config_files(game_folder) {
+		if game_folder.join(&file).is_file() {
+			let destination = framework_folder.join(VANILLA_BACKUP_FOLDER).join(&file);
+
+			fs::create_dir_all(destination.parent().context("Backup destination parent")?)
+				.context("Creating vanilla backup folder")?;
+
+			fs::copy(game_folder.join(&file), &destination)
+				.with_context(|| format!("Backing up {}", file.display()))?;
+
+			backed_up_files.push(file);
+		}
+	}
+
+	Ok(Baseline {
+		chunks,
+		files: record_files(game_folder)?,
+		backed_up_files
+	})
+}
+
+pub fn read_receipt(framework_folder: &Path) -> anyhow::Result<Option<InstallReceipt>> {
+	let path = framework_folder.join(RECEIPT_FILE);
+
+	if !path.is_file() {
+		return Ok(None);
+	}
+
+	Ok(Some(
+		serde_json::from_slice(&fs::read(&path).context("Reading install receipt")?)
+			.context("Install receipt as JSON")?
+	))
+}
+
+pub fn write_receipt(framework_folder: &Path, receipt: &InstallReceipt) -> anyhow::Result<()> {
+	fs::write(
+		framework_folder.join(RECEIPT_FILE),
+		serde_json::to_vec_pretty(receipt).context("Serialising install receipt")?
+	)
+	.context("Writing install receipt")
+}
@@ -0,0 +1,241 @@
+use std::{
+	collections::VecDeque,
+	fs,
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		mpsc::{channel, Receiver},
+		Arc
+	},
+	thread
+};
+
+use sysinfo::{DiskExt, System, SystemExt};
+
+use crate::{
+	detection::{Candidate, Source},
+	game_folder::{GameFolder, GameFolderCheck}
+};
+
+/// How many folders deep below a well-known location the scan will look for the game.
+const MAX_DEPTH: usize = 3;
+
+/// Folders, relative to a volume root, that games are commonly installed to.
+const WELL_KNOWN_LOCATIONS: &[&str] = &[
+	"Program Files (x86)/Steam/steamapps/common",
+	"Program Files/Steam/steamapps/common",
+	"Program Files/Epic Games",
+	"Program Files (x86)/Epic Games",
+	"Program Files/ModifiableWindowsApps",
+	"SteamLibrary/steamapps/common",
+	"Steam/steamapps/common",
+	"Games/SteamLibrary/steamapps/common",
+	"Games/Steam/steamapps/common",
+	"XboxGames",
+	"Epic Games",
+	"Games/Epic Games",
+	"Games",
+	".local/share/Steam/steamapps/common",
+	".steam/steam/steamapps/common",
+	".var/app/com.valvesoftware.Steam/.local/share/Steam/steamapps/common",
+	"Games/Heroic"
+];
+
+pub enum ScanEvent {
+	/// The scan has moved on to a new well-known location.
+	Scanning(PathBuf),
+
+	/// A folder containing HITMAN 3 was found.
+	Found(Box<GameFolder>),
+
+	Finished
+}
+
+/// A scan of every mounted volume for HITMAN 3, running on a background thread.
+pub struct DriveScan {
+	receiver: Receiver<ScanEvent>,
+	cancelled: Arc<AtomicBool>,
+	pub current_location: Option<PathBuf>,
+	pub finished: bool
+}
+
+impl DriveScan {
+	/// Start scanning; `on_event` is called from the scanning thread whenever there's something new
+	/// to [`poll`](Self::poll).
+	pub fn start(on_event: impl Fn() + Send + 'static) -> Self {
+		let (sender, receiver) = channel();
+		let cancelled = Arc::new(AtomicBool::new(false));
+
+		{
+			let cancelled = cancelled.clone();
+
+			thread::spawn(move || {
+				for location in scan_locations() {
+					if cancelled.load(Ordering::Relaxed) {
+						break;
+					}
+
+					if sender
+						.send(ScanEvent::Scanning(location.to_owned()))
+						.is_err()
+					{
+						return;
+					}
+
+					on_event();
+
+					scan_location(&location, &cancelled, |path| {
+						let folder = GameFolder::inspect(Candidate {
+							path,
+							username: None,
+							source: Source::DriveScan
+						});
+
+						let _ = sender.send(ScanEvent::Found(Box::new(folder)));
+						on_event();
+					});
+				}
+
+				let _ = sender.send(ScanEvent::Finished);
+				on_event();
+			});
+		}
+
+		Self {
+			receiver,
+			cancelled,
+			current_location: None,
+			finished: false
+		}
+	}
+
+	/// Stop the scan as soon as possible; no more folders will be reported after the scan thread
+	/// notices.
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::Relaxed)
+	}
+
+	/// Collect the folders found since the last poll.
+	pub fn poll(&mut self) -> Vec<GameFolder> {
+		let mut found = vec![];
+
+		while let Ok(event) = self.receiver.try_recv() {
+			match event {
+				ScanEvent::Scanning(location) => {
+					self.current_location = Some(location);
+				}
+
+				ScanEvent::Found(folder) => {
+					found.push(*folder);
+				}
+
+				ScanEvent::Finished => {
+					self.current_location = None;
+					self.finished = true;
+				}
+			}
+		}
+
+		found
+	}
+}
+
+impl Drop for DriveScan {
+	fn drop(&mut self) {
+		self.cancel();
+	}
+}
+
+/// The roots of every mounted volume, plus the places Linux distributions commonly mount extra
+/// drives and the user's home folder.
+pub fn volume_roots() -> Vec<PathBuf> {
+	let mut system = System::new();
+	system.refresh_disks_list();
+
+	let mut roots = system
+		.disks()
+		.iter()
+		.map(|x| x.mount_point().to_owned())
+		.collect::<Vec<_>>();
+
+	if cfg!(not(windows)) {
+		let mut mount_parents = vec![PathBuf::from("/mnt"), PathBuf::from("/media")];
+
+		if let Ok(user) = std::env::var("USER") {
+			mount_parents.push(Path::new("/run/media").join(&user));
+			mount_parents.push(Path::new("/media").join(&user));
+		}
+
+		for parent in mount_parents {
+			if let Ok(entries) = fs::read_dir(parent) {
+				roots.extend(
+					entries
+						.filter_map(|x| x.ok())
+						.map(|x| x.path())
+						.filter(|x| x.is_dir())
+				);
+			}
+		}
+
+		if let Some(home) = dirs::home_dir() {
+			roots.push(home);
+		}
+	}
+
+	let mut unique_roots = vec![];
+
+	for root in roots {
+		if !unique_roots.contains(&root) {
+			unique_roots.push(root);
+		}
+	}
+
+	unique_roots
+}
+
+/// Every well-known location that exists on a mounted volume.
+pub fn scan_locations() -> Vec<PathBuf> {
+	volume_roots()
+		.iter()
+		.flat_map(|root| WELL_KNOWN_LOCATIONS.iter().map(move |x| root.join(x)))
+		.filter(|x| x.is_dir())
+		.collect()
+}
+
+/// Walk a location up to [`MAX_DEPTH`] folders deep, reporting every HITMAN 3 folder found. The
+/// walk doesn't descend into a game folder once it's been found, and doesn't follow symlinks below
+/// the location itself.
+pub fn scan_location(location: &Path, cancelled: &AtomicBool, mut on_found: impl FnMut(PathBuf)) {
+	let mut queue = VecDeque::from([(location.to_owned(), 0)]);
+
+	while let Some((folder, depth)) = queue.pop_front() {
+		if cancelled.load(Ordering::Relaxed) {
+			return;
+		}
+
+		if GameFolderCheck::new(&folder).is_game_folder() {
+			on_found(folder);
+			continue;
+		}
+
+		// Microsoft installs keep the game in a Content subfolder
+		if GameFolderCheck::new(&folder.join("Content")).is_game_folder() {
+			on_found(folder.join("Content"));
+			continue;
+		}
+
+		if depth < MAX_DEPTH {
+			if let Ok(entries) = fs::read_dir(&folder) {
+				for entry in entries.filter_map(|x| x.ok()) {
+					if entry.file_type().map(|x| x.is_dir()).unwrap_or(false) {
+						queue.push_back((entry.path(), depth + 1));
+					}
+				}
+			}
+		}
+	}
+}
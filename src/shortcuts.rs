@@ -0,0 +1,492 @@
+use std::{
+	fs,
+	path::{Path, PathBuf}
+};
+
+use anyhow::Context;
+use mslnk::ShellLink;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShortcutLocation {
+	/// The Start menu on Windows, or the applications menu on Linux.
+	StartMenu,
+	Desktop
+}
+
+impl ShortcutLocation {
+	/// Where the shortcut goes, in the platform's words, for showing to the user.
+	pub fn name(&self) -> &'static str {
+		match self {
+			ShortcutLocation::StartMenu if cfg!(windows) => "the Start menu",
+			ShortcutLocation::StartMenu => "the applications menu",
+			ShortcutLocation::Desktop => "the desktop"
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShortcutScope {
+	CurrentUser,
+	AllUsers
+}
+
+/// A shortcut to a program, independent of how the platform stores shortcuts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shortcut {
+	/// Shown in menus and used as the file name.
+	pub name: String,
+	pub description: String,
+	pub target: PathBuf,
+	pub arguments: Vec<String>,
+	pub working_directory: Option<PathBuf>,
+
+	/// Windows shortcuts use the target's icon when there isn't one.
+	pub icon: Option<PathBuf>
+}
+
+/// The folders shortcuts go in. Constructed by hand to point shortcuts somewhere other than the
+/// real system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShortcutFolders {
+	pub start_menu: PathBuf,
+	pub all_users_start_menu: Option<PathBuf>,
+	pub desktop: PathBuf,
+	pub all_users_desktop: Option<PathBuf>
+}
+
+impl ShortcutFolders {
+	/// The Start menu Programs and Desktop known folders.
+	#[cfg(windows)]
+	pub fn system() -> anyhow::Result<Self> {
+		Ok(Self {
+			start_menu: known_folder(&FOLDERID_PROGRAMS).context("Start menu folder")?,
+			all_users_start_menu: known_folder(&FOLDERID_COMMON_PROGRAMS),
+			desktop: known_folder(&FOLDERID_DESKTOP).context("Desktop folder")?,
+			all_users_desktop: known_folder(&FOLDERID_PUBLIC_DESKTOP)
+		})
+	}
+
+	/// The XDG applications and desktop folders. There's no desktop shared between users.
+	#[cfg(not(windows))]
+	pub fn system() -> anyhow::Result<Self> {
+		let home = dirs::home_dir().context("home dir")?;
+
+		Ok(Self {
+			start_menu: dirs::data_dir()
+				.unwrap_or_else(|| home.join(".local").join("share"))
+				.join("applications"),
+			all_users_start_menu: Some(PathBuf::from("/usr/local/share/applications")),
+			desktop: dirs::desktop_dir().unwrap_or_else(|| home.join("Desktop")),
+			all_users_desktop: None
+		})
+	}
+
+	pub fn folder(
+		&self,
+		location: ShortcutLocation,
+		scope: ShortcutScope
+	) -> anyhow::Result<PathBuf> {
+		match (location, scope) {
+			(ShortcutLocation::StartMenu, ShortcutScope::CurrentUser) => {
+				Ok(self.start_menu.to_owned())
+			}
+			(ShortcutLocation::Desktop, ShortcutScope::CurrentUser) => Ok(self.desktop.to_owned()),
+
+			(ShortcutLocation::StartMenu, ShortcutScope::AllUsers) => self
+				.all_users_start_menu
+				.to_owned()
+				.context("There's no Start menu shared between users on this system"),
+
+			(ShortcutLocation::Desktop, ShortcutScope::AllUsers) => self
+				.all_users_desktop
+				.to_owned()
+				.context("There's no desktop shared between users on this system")
+		}
+	}
+}
+
+/// A way of storing shortcuts on disk.
+pub trait ShortcutBackend {
+	fn folders(&self) -> &ShortcutFolders;
+
+	/// The name of the shortcut's file, including its extension.
+	fn file_name(&self, shortcut: &Shortcut) -> String;
+
+	fn write(&self, shortcut: &Shortcut, path: &Path) -> anyhow::Result<()>;
+
+	/// Where the shortcut goes, whether or not it exists.
+	fn path(
+		&self,
+		shortcut: &Shortcut,
+		location: ShortcutLocation,
+		scope: ShortcutScope
+	) -> anyhow::Result<PathBuf> {
+		Ok(self
+			.folders()
+			.folder(location, scope)?
+			.join(self.file_name(shortcut)))
+	}
+
+	/// Create the shortcut, replacing any existing one, and return its path.
+	fn create(
+		&self,
+		shortcut: &Shortcut,
+		location: ShortcutLocation,
+		scope: ShortcutScope
+	) -> anyhow::Result<PathBuf> {
+		let path = self.path(shortcut, location, scope)?;
+
+		fs::create_dir_all(path.parent().context("Shortcut parent")?)
+			.context("Creating shortcut folder")?;
+
+		self.write(shortcut, &path)
+			.with_context(|| format!("Creating shortcut at {}", path.display()))?;
+
+		Ok(path)
+	}
+}
+
+/// Windows .lnk files.
+pub struct LnkBackend {
+	pub folders: ShortcutFolders
+}
+
+impl ShortcutBackend for LnkBackend {
+	fn folders(&self) -> &ShortcutFolders {
+		&self.folders
+	}
+
+	fn file_name(&self, shortcut: &Shortcut) -> String {
+		format!("{}.lnk", shortcut.name)
+	}
+
+	fn write(&self, shortcut: &Shortcut, path: &Path) -> anyhow::Result<()> {
+		let mut link = ShellLink::new(shortcut.target.to_str().context("linktarget to_str")?)?;
+
+		link.set_name(Some(shortcut.description.to_owned()));
+
+		if !shortcut.arguments.is_empty() {
+			link.set_arguments(Some(
+				shortcut
+					.arguments
+					.iter()
+					.map(|x| format!("\"{x}\""))
+					.collect::<Vec<_>>()
+					.join(" ")
+			));
+		}
+
+		link.set_working_dir(
+			shortcut
+				.working_directory
+				.as_ref()
+				.map(|x| x.to_string_lossy().into_owned())
+		);
+
+		link.set_icon_location(
+			shortcut
+				.icon
+				.as_ref()
+				.map(|x| x.to_string_lossy().into_owned())
+		);
+
+		link.create_lnk(path)?;
+
+		Ok(())
+	}
+}
+
+/// freedesktop.org .desktop entries.
+pub struct DesktopEntryBackend {
+	pub folders: ShortcutFolders
+}
+
+impl ShortcutBackend for DesktopEntryBackend {
+	fn folders(&self) -> &ShortcutFolders {
+		&self.folders
+	}
+
+	fn file_name(&self, shortcut: &Shortcut) -> String {
+		format!("{}.desktop", shortcut.name.replace(' ', "-").to_lowercase())
+	}
+
+	fn write(&self, shortcut: &Shortcut, path: &Path) -> anyhow::Result<()> {
+		fs::write(path, desktop_entry(shortcut))?;
+
+		// File managers only run desktop entries on the desktop if they're executable
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+
+			fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+		}
+
+		Ok(())
+	}
+}
+
+/// The backend for the platform the installer is running on.
+pub fn system_backend() -> anyhow::Result<Box<dyn ShortcutBackend>> {
+	let folders = ShortcutFolders::system()?;
+
+	Ok(if cfg!(windows) {
+		Box::new(LnkBackend { folders })
+	} else {
+		Box::new(DesktopEntryBackend { folders })
+	})
+}
+
+/// The contents of a .desktop file for a shortcut.
+pub fn desktop_entry(shortcut: &Shortcut) -> String {
+	let mut entry = format!(
+		"[Desktop Entry]\nType=Application\nName={}\nComment={}\nExec={}\n",
+		escape_value(&shortcut.name),
+		escape_value(&shortcut.description),
+		escape_value(
+			&std::iter::once(shortcut.target.to_string_lossy().into_owned())
+				.chain(shortcut.arguments.iter().cloned())
+				.map(|x| quote_exec_argument(&x))
+				.collect::<Vec<_>>()
+				.join(" ")
+		)
+	);
+
+	if let Some(working_directory) = &shortcut.working_directory {
+		entry += &format!(
+			"Path={}\n",
+			escape_value(&working_directory.to_string_lossy())
+		);
+	}
+
+	if let Some(icon) = &shortcut.icon {
+		entry += &format!("Icon={}\n", escape_value(&icon.to_string_lossy()));
+	}
+
+	entry += "Terminal=false\nCategories=Game;\n";
+
+	entry
+}
+
+/// Quote an argument for the Exec key, which has its own quoting rules on top of the value
+/// escaping every key has.
+fn quote_exec_argument(argument: &str) -> String {
+	let mut quoted = String::from("\"");
+
+	for c in argument.chars() {
+		match c {
+			'"' | '`' | '$' | '\\' => {
+				quoted.push('\\');
+				quoted.push(c);
+			}
+
+			// Field codes like %f would be expanded by the launcher
+			'%' => quoted.push_str("%%"),
+
+			_ => quoted.push(c)
+		}
+	}
+
+	quoted.push('"');
+	quoted
+}
+
+fn escape_value(value: &str) -> String {
+	value
+		.replace('\\', "\\\\")
+		.replace('\n', "\\n")
+		.replace('\t', "\\t")
+}
+
+#[cfg(windows)]
+#[repr(C)]
+struct Guid(u32, u16, u16, [u8; 8]);
+
+#[cfg(windows)]
+const FOLDERID_PROGRAMS: Guid = Guid(
+	0xA77F5D77,
+	0x2E2B,
+	0x44C3,
+	[0xA6, 0xA2, 0xAB, 0xA6, 0x01, 0x05, 0x4A, 0x51]
+);
+
+#[cfg(windows)]
+const FOLDERID_COMMON_PROGRAMS: Guid = Guid(
+	0x0139D44E,
+	0x6AFE,
+	0x49F2,
+	[0x86, 0x90, 0x3D, 0xAF, 0xCA, 0xE6, 0xFF, 0xB8]
+);
+
+#[cfg(windows)]
+const FOLDERID_DESKTOP: Guid = Guid(
+	0xB4BFCC3A,
+	0xDB2C,
+	0x424C,
+	[0xB0, 0x29, 0x7F, 0xE9, 0x9A, 0x87, 0xC6, 0x41]
+);
+
+#[cfg(windows)]
+const FOLDERID_PUBLIC_DESKTOP: Guid = Guid(
+	0xC4AA340D,
+	0xF20F,
+	0x4863,
+	[0xAF, 0xEF, 0xF8, 0x7E, 0xF2, 0xE6, 0xBA, 0x25]
+);
+
+#[cfg(windows)]
+fn known_folder(id: &Guid) -> Option<PathBuf> {
+	use std::{ffi::OsString, os::windows::ffi::OsStringExt, ptr, slice};
+
+	#[link(name = "shell32")]
+	extern "system" {
+		fn SHGetKnownFolderPath(
+			id: *const Guid,
+			flags: u32,
+			token: isize,
+			path: *mut *mut u16
+		) -> i32;
+	}
+
+	#[link(name = "ole32")]
+	extern "system" {
+		fn CoTaskMemFree(memory: *mut u16);
+	}
+
+	unsafe {
+		let mut path = ptr::null_mut();
+
+		let result = SHGetKnownFolderPath(id, 0, 0, &mut path);
+
+		let folder = if result == 0 {
+			let len = (0..).take_while(|&x| *path.add(x) != 0).count();
+			Some(PathBuf::from(OsString::from_wide(slice::from_raw_parts(
+				path, len
+			))))
+		} else {
+			None
+		};
+
+		// Freed even on failure
+		CoTaskMemFree(path);
+
+		folder
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_util::temp_folder;
+
+	fn shortcut() -> Shortcut {
+		Shortcut {
+			name: "Simple Mod Framework".to_owned(),
+			description: "Manage and deploy HITMAN 3 mods".to_owned(),
+			target: PathBuf::from("/games/HITMAN 3/Simple Mod Framework/launch.sh"),
+			arguments: vec![],
+			working_directory: Some(PathBuf::from("/games/HITMAN 3/Simple Mod Framework")),
+			icon: Some(PathBuf::from(
+				"/games/HITMAN 3/Simple Mod Framework/icon.png"
+			))
+		}
+	}
+
+	#[test]
+	fn desktop_entry_contents() {
+		assert_eq!(
+			desktop_entry(&shortcut()),
+			"[Desktop Entry]\nType=Application\nName=Simple Mod Framework\nComment=Manage and \
+			 deploy HITMAN 3 mods\nExec=\"/games/HITMAN 3/Simple Mod \
+			 Framework/launch.sh\"\nPath=/games/HITMAN 3/Simple Mod Framework\nIcon=/games/HITMAN \
+			 3/Simple Mod Framework/icon.png\nTerminal=false\nCategories=Game;\n"
+		);
+	}
+
+	#[test]
+	fn desktop_entry_quotes_exec() {
+		let shortcut = Shortcut {
+			target: PathBuf::from("/games/100% HITMAN/run"),
+			arguments: vec![
+				"say \"hi\"".to_owned(),
+				r"C:\Games".to_owned(),
+				"$HOME".to_owned(),
+			],
+			working_directory: None,
+			icon: None,
+			..shortcut()
+		};
+
+		let entry = desktop_entry(&shortcut);
+
+		// Backslashes are escaped once for the Exec quoting and again for the value
+		assert!(entry.contains(
+			"\nExec=\"/games/100%% HITMAN/run\" \"say \\\\\"hi\\\\\"\" \"C:\\\\\\\\Games\" \
+			 \"\\\\$HOME\"\n"
+		));
+		assert!(!entry.contains("\nPath="));
+		assert!(!entry.contains("\nIcon="));
+	}
+
+	#[test]
+	fn desktop_entry_backend_creates() {
+		let folder = temp_folder("shortcuts-create");
+
+		let backend = DesktopEntryBackend {
+			folders: ShortcutFolders {
+				start_menu: folder.join("applications"),
+				all_users_start_menu: None,
+				desktop: folder.join("Desktop"),
+				all_users_desktop: None
+			}
+		};
+
+		let path = backend
+			.create(
+				&shortcut(),
+				ShortcutLocation::StartMenu,
+				ShortcutScope::CurrentUser
+			)
+			.unwrap();
+
+		assert_eq!(
+			path,
+			folder
+				.join("applications")
+				.join("simple-mod-framework.desktop")
+		);
+		assert_eq!(
+			fs::read_to_string(&path).unwrap(),
+			desktop_entry(&shortcut())
+		);
+
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+
+			assert_eq!(
+				fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+				0o755
+			);
+		}
+
+		// Creating it again replaces it
+		backend
+			.create(
+				&shortcut(),
+				ShortcutLocation::StartMenu,
+				ShortcutScope::CurrentUser
+			)
+			.unwrap();
+
+		assert!(backend
+			.create(
+				&shortcut(),
+				ShortcutLocation::Desktop,
+				ShortcutScope::AllUsers
+			)
+			.is_err());
+		assert!(!folder.join("Desktop").exists());
+
+		fs::remove_dir_all(folder).unwrap();
+	}
+}
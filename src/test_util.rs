@@ -0,0 +1,12 @@
+use std::{fs, path::PathBuf};
+
+/// An empty folder for a test to write to, unique to the test and this run.
+pub fn temp_folder(name: &str) -> PathBuf {
+	let folder =
+		std::env::temp_dir().join(format!("smf-installer-test-{name}-{}", std::process::id()));
+
+	let _ = fs::remove_dir_all(&folder);
+	fs::create_dir_all(&folder).unwrap();
+
+	folder
+}
@@ -0,0 +1,209 @@
+use std::{
+	fs,
+	path::{Path, PathBuf}
+};
+
+use anyhow::Context;
+
+use crate::{
+	conflicts::{is_encrypted, parse_chunk_name, FIRST_MODDED_PATCH_LEVEL},
+	error::{file_error, InstallerError},
+	game_folder::runtime_folder,
+	install::mod_manager_shortcut,
+	processes::ensure_not_running,
+	progress::{CancellationToken, Progress, Step},
+	receipt::{config_files, read_receipt, VANILLA_BACKUP_FOLDER},
+	shortcuts::{system_backend, ShortcutLocation, ShortcutScope}
+};
+
+/// What uninstalling changed, and what it couldn't put back.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct UninstallReport {
+	pub removed: Vec<PathBuf>,
+	pub restored: Vec<PathBuf>,
+	pub problems: Vec<String>
+}
+
+/// Remove the framework from a game folder and put the game back the way it was before the
+/// framework was installed, going by the install receipt.
+///
+/// Installs made without a receipt fall back to removing patch chunks only mod tools create.
+/// Anything that can't be reverted is listed in the report rather than failing the uninstall.
+/// Cancelling stops between steps, leaving the framework folder in place to uninstall again.
+pub fn uninstall(
+	game_folder: &Path,
+	progress: &mut dyn FnMut(Progress),
+	cancel: &CancellationToken
+) -> Result<UninstallReport, InstallerError> {
+	let framework_folder = game_folder.join("Simple Mod Framework");
+
+	if !framework_folder.is_dir() {
+		return Err(InstallerError::NotInstalled {
+			path: game_folder.to_owned()
+		});
+	}
+
+	ensure_not_running(game_folder)?;
+
+	let mut report = UninstallReport::default();
+
+	let receipt = match read_receipt(&framework_folder) {
+		Ok(x) => x,
+		Err(e) => {
+			report.problems.push(format!(
+				"The install receipt couldn't be read ({e:#}), so deployed files were found by \
+				 name instead"
+			));
+
+			None
+		}
+	};
+
+	// Remove deployed chunks
+	cancel.check()?;
+	progress(Progress::Step(Step::RemovingDeployedFiles));
+
+	let runtime = runtime_folder(game_folder);
+
+	for entry in fs::read_dir(&runtime)
+		.context("Reading Runtime")
+		.map_err(file_error(&runtime))?
+		.filter_map(|x| x.ok())
+	{
+		let name = entry.file_name().to_string_lossy().into_owned();
+
+		let chunk = parse_chunk_name(&name);
+
+		let modded_patch_level = chunk
+			.and_then(|(_, patch)| patch)
+			.map_or(false, |x| x >= FIRST_MODDED_PATCH_LEVEL);
+
+		let in_baseline = receipt.as_ref().map(|x| x.baseline.chunks.contains(&name));
+
+		if chunk.is_none() || in_baseline == Some(true) {
+			continue;
+		}
+
+		if modded_patch_level {
+			match fs::remove_file(entry.path()) {
+				Ok(_) => report.removed.push(entry.path()),
+				Err(e) => report
+					.problems
+					.push(format!("Couldn't remove {}: {e}", entry.path().display()))
+			}
+		} else if in_baseline == Some(false) {
+			report.problems.push(format!(
+				"{} was added after the framework was installed, but it looks like part of a game \
+				 update so it was left in place",
+				entry.path().display()
+			));
+		}
+	}
+
+	// Restore the game's config files
+	cancel.check()?;
+	progress(Progress::Step(Step::RestoringConfig));
+
+	match &receipt {
+		Some(receipt) => {
+			for file in &receipt.baseline.backed_up_files {
+				let backup = framework_folder.join(VANILLA_BACKUP_FOLDER).join(file);
+				let current = game_folder.join(file);
+
+				// An encrypted file is the game's own, which may be newer than the backup if the
+				// game has updated since
+				if current.is_file() && is_encrypted(&current) {
+					if fs::read(&backup).ok() != fs::read(&current).ok() {
+						report.problems.push(format!(
+							"{} wasn't restored as it's already the game's own version",
+							current.display()
+						));
+					}
+				} else if fs::read(&backup).ok() != fs::read(&current).ok() {
+					match fs::copy(&backup, &current) {
+						Ok(_) => report.restored.push(current),
+						Err(e) => report
+							.problems
+							.push(format!("Couldn't restore {}: {e}", current.display()))
+					}
+				}
+			}
+		}
+
+		None => {
+			for file in config_files(game_folder) {
+				if game_folder.join(&file).is_file() && !is_encrypted(&game_folder.join(&file)) {
+					report.problems.push(format!(
+						"{} has been modified and there's no backup of the original; verify the \
+						 game files through your store to restore it",
+						file.display()
+					));
+				}
+			}
+		}
+	}
+
+	// Receipts from before shortcuts were recorded, or no receipt at all, mean the only shortcut
+	// is the current user's Start menu one
+	cancel.check()?;
+	progress(Progress::Step(Step::RemovingShortcuts));
+
+	let shortcuts = match receipt.as_ref().and_then(|x| x.shortcuts.as_ref()) {
+		Some(shortcuts) => shortcuts.to_owned(),
+
+		None => system_backend()
+			.and_then(|x| {
+				x.path(
+					&mod_manager_shortcut(&framework_folder),
+					ShortcutLocation::StartMenu,
+					ShortcutScope::CurrentUser
+				)
+			})
+			.into_iter()
+			.collect()
+	};
+
+	for shortcut in shortcuts {
+		if shortcut.is_file() {
+			match fs::remove_file(&shortcut) {
+				Ok(_) => report.removed.push(shortcut),
+				Err(e) => report
+					.problems
+					.push(format!("Couldn't remove {}: {e}", shortcut.display()))
+			}
+		}
+	}
+
+	cancel.check()?;
+	progress(Progress::Step(Step::RemovingFramework));
+
+	if let Err(e) = fs::remove_dir_all(&framework_folder) {
+		report.problems.push(format!(
+			"Couldn't remove {}: {e}",
+			framework_folder.display()
+		));
+	} else {
+		report.removed.push(framework_folder);
+	}
+
+	Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_util::temp_folder;
+
+	#[test]
+	fn nothing_to_uninstall_without_framework() {
+		let game_folder = temp_folder("uninstall-not-installed");
+
+		assert!(matches!(
+			uninstall(&game_folder, &mut |_| {}, &CancellationToken::new()),
+			Err(InstallerError::NotInstalled { .. })
+		));
+
+		fs::remove_dir_all(game_folder).unwrap();
+	}
+}
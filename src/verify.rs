@@ -0,0 +1,100 @@
+use std::{
+	collections::HashMap,
+	fmt,
+	path::{Path, PathBuf}
+};
+
+use anyhow::{bail, Context};
+
+use crate::receipt::{read_receipt, record_files};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+	Added,
+	Removed,
+	Resized { before: u64, after: u64 },
+	Modified
+}
+
+/// A file that's different from when the framework was installed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Difference {
+	/// Relative to the game folder.
+	pub path: PathBuf,
+	pub change: Change
+}
+
+impl fmt::Display for Difference {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.change {
+			Change::Added => write!(f, "added: {}", self.path.display()),
+			Change::Removed => write!(f, "removed: {}", self.path.display()),
+			Change::Resized { before, after } => write!(
+				f,
+				"changed size: {} ({before} -> {after} bytes)",
+				self.path.display()
+			),
+			Change::Modified => write!(f, "modified: {}", self.path.display())
+		}
+	}
+}
+
+/// Compare a game folder with the baseline recorded when the framework was installed into it.
+pub fn verify_game(game_folder: &Path) -> anyhow::Result<Vec<Difference>> {
+	let receipt = match read_receipt(&game_folder.join("Simple Mod Framework"))? {
+		Some(x) => x,
+		None => bail!(
+			"There's no install receipt in {}; the framework was installed before receipts were \
+			 kept, or wasn't installed by the installer",
+			game_folder.display()
+		)
+	};
+
+	let current = record_files(game_folder)
+		.context("Listing game files")?
+		.into_iter()
+		.map(|x| (x.path.to_owned(), x))
+		.collect::<HashMap<_, _>>();
+
+	let mut differences = vec![];
+
+	for before in &receipt.baseline.files {
+		let change = match current.get(&before.path) {
+			None => Some(Change::Removed),
+
+			Some(after) if after.size != before.size => Some(Change::Resized {
+				before: before.size,
+				after: after.size
+			}),
+
+			// Config files are often edited without changing size
+			Some(after) if before.sha256.is_some() && after.sha256 != before.sha256 => {
+				Some(Change::Modified)
+			}
+
+			Some(_) => None
+		};
+
+		if let Some(change) = change {
+			differences.push(Difference {
+				path: before.path.to_owned(),
+				change
+			});
+		}
+	}
+
+	let mut added = current
+		.into_keys()
+		.filter(|x| !receipt.baseline.files.iter().any(|y| y.path == *x))
+		.map(|path| Difference {
+			path,
+			change: Change::Added
+		})
+		.collect::<Vec<_>>();
+
+	added.sort_by(|a, b| a.path.cmp(&b.path));
+	differences.extend(added);
+
+	Ok(differences)
+}
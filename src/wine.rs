@@ -0,0 +1,318 @@
+use std::{
+	fs,
+	path::{Path, PathBuf}
+};
+
+use anyhow::Context;
+use serde_json::Value;
+
+use crate::detection::steam::{steam_roots, APP_IDS};
+
+/// The name of the launcher script, in the framework folder.
+pub const LAUNCHER_SCRIPT: &str = "Mod Manager.sh";
+
+/// HITMAN 3's app name in Legendary and Heroic.
+const EPIC_APP_NAME: &str = "Eider";
+
+/// Something that can run Windows programs in HITMAN 3's prefix on Linux.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WineRuntime {
+	/// Steam's Proton, or Heroic running a copy of Proton.
+	Proton {
+		proton: PathBuf,
+
+		/// The folder containing the prefix (`pfx`), like `steamapps/compatdata/1659040`.
+		compat_data: PathBuf,
+		steam_root: Option<PathBuf>
+	},
+
+	Wine {
+		wine: PathBuf,
+
+		/// Wine's default prefix is used when there's none.
+		prefix: Option<PathBuf>
+	}
+}
+
+impl WineRuntime {
+	/// Find what the game itself runs with: Steam's Proton for a Steam library, Heroic's
+	/// configuration for an Epic copy, or the system Wine if neither is found.
+	pub fn detect(game_folder: &Path) -> Self {
+		proton_runtime(game_folder)
+			.or_else(heroic_runtime)
+			.unwrap_or_else(|| WineRuntime::Wine {
+				wine: PathBuf::from("wine"),
+				prefix: std::env::var_os("WINEPREFIX").map(PathBuf::from)
+			})
+	}
+
+	pub fn description(&self) -> String {
+		match self {
+			WineRuntime::Proton {
+				proton,
+				compat_data,
+				..
+			} => format!(
+				"{} with the prefix in {}",
+				proton
+					.parent()
+					.and_then(|x| x.file_name())
+					.map_or_else(|| "Proton".into(), |x| x.to_string_lossy()),
+				compat_data.display()
+			),
+
+			WineRuntime::Wine { wine, prefix } => format!(
+				"{} with {}",
+				wine.display(),
+				prefix.as_ref().map_or_else(
+					|| "the default prefix".to_owned(),
+					|x| format!("the prefix in {}", x.display())
+				)
+			)
+		}
+	}
+
+	/// The environment variables to set and the command to put before the Windows program.
+	fn command(&self) -> (Vec<(&'static str, PathBuf)>, Vec<PathBuf>) {
+		match self {
+			WineRuntime::Proton {
+				proton,
+				compat_data,
+				steam_root
+			} => (
+				[
+					Some(("STEAM_COMPAT_DATA_PATH", compat_data.to_owned())),
+					steam_root
+						.as_ref()
+						.map(|x| ("STEAM_COMPAT_CLIENT_INSTALL_PATH", x.to_owned()))
+				]
+				.into_iter()
+				.flatten()
+				.collect(),
+				vec![proton.to_owned(), PathBuf::from("run")]
+			),
+
+			WineRuntime::Wine { wine, prefix } => (
+				prefix
+					.as_ref()
+					.map(|x| ("WINEPREFIX", x.to_owned()))
+					.into_iter()
+					.collect(),
+				vec![wine.to_owned()]
+			)
+		}
+	}
+
+	/// A shell script that runs a Windows program in this runtime, passing on any arguments.
+	pub fn launcher_script(&self, program: &Path) -> String {
+		let (environment, command) = self.command();
+
+		let mut script = String::from(
+			"#!/bin/sh\n# Generated by the Simple Mod Framework installer to run the Mod Manager \
+			 in HITMAN 3's prefix\n"
+		);
+
+		for (name, value) in environment {
+			script += &format!("export {name}={}\n", shell_quote(&value));
+		}
+
+		if let Some(folder) = program.parent() {
+			script += &format!("cd {}\n", shell_quote(folder));
+		}
+
+		script += &format!(
+			"exec {} {} \"$@\"\n",
+			command
+				.iter()
+				.map(|x| shell_quote(x))
+				.collect::<Vec<_>>()
+				.join(" "),
+			shell_quote(program)
+		);
+
+		script
+	}
+}
+
+/// Write the launcher script for the Mod Manager into a framework folder and return its path.
+pub fn write_launcher_script(
+	framework_folder: &Path,
+	runtime: &WineRuntime
+) -> anyhow::Result<PathBuf> {
+	let path = framework_folder.join(LAUNCHER_SCRIPT);
+
+	fs::write(
+		&path,
+		runtime.launcher_script(&framework_folder.join("Mod Manager").join("Mod Manager.exe"))
+	)
+	.context("Writing launcher script")?;
+
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+
+		fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+			.context("Making launcher script executable")?;
+	}
+
+	Ok(path)
+}
+
+fn shell_quote(value: &Path) -> String {
+	format!("'{}'", value.to_string_lossy().replace('\'', r"'\''"))
+}
+
+/// Proton for a game in a Steam library, from the library's compatdata folder.
+fn proton_runtime(game_folder: &Path) -> Option<WineRuntime> {
+	// The game folder is steamapps/common/HITMAN 3
+	let steamapps = game_folder.parent()?.parent()?;
+
+	if steamapps.file_name()? != "steamapps" {
+		return None;
+	}
+
+	let compat_data = APP_IDS
+		.iter()
+		.map(|x| steamapps.join("compatdata").join(x))
+		.find(|x| x.join("pfx").is_dir())?;
+
+	let steam_roots = steam_roots().unwrap_or_default();
+
+	let proton = configured_proton(&compat_data).or_else(|| newest_proton(&steam_roots))?;
+
+	Some(WineRuntime::Proton {
+		proton,
+		compat_data,
+		steam_root: steam_roots.into_iter().next()
+	})
+}
+
+/// The Proton the prefix was last run with. config_info lists paths inside the Proton install,
+/// like `.../common/Proton 8.0/files/share/fonts/`.
+fn configured_proton(compat_data: &Path) -> Option<PathBuf> {
+	fs::read_to_string(compat_data.join("config_info"))
+		.ok()?
+		.lines()
+		.filter_map(|line| {
+			["/files/", "/dist/"]
+				.iter()
+				.find_map(|x| line.find(x))
+				.map(|x| Path::new(&line[..x]).join("proton"))
+		})
+		.find(|x| x.is_file())
+}
+
+/// The Proton with the highest version installed through Steam or as a custom compatibility tool.
+fn newest_proton(steam_roots: &[PathBuf]) -> Option<PathBuf> {
+	steam_roots
+		.iter()
+		.flat_map(|root| {
+			[
+				root.join("steamapps").join("common"),
+				root.join("compatibilitytools.d")
+			]
+		})
+		.filter_map(|x| fs::read_dir(x).ok())
+		.flatten()
+		.filter_map(|x| x.ok())
+		.filter(|x| {
+			let name = x.file_name().to_string_lossy().to_lowercase();
+			name.contains("proton") && x.path().join("proton").is_file()
+		})
+		.max_by_key(|x| proton_version(&x.file_name().to_string_lossy()))
+		.map(|x| x.path().join("proton"))
+}
+
+/// The numbers in a Proton folder's name, like `[9, 0]` for "Proton 9.0 (Beta)" or `[9, 20]` for
+/// "GE-Proton9-20", for comparing versions. Folders without a version, like "Proton -
+/// Experimental", sort below every numbered one.
+fn proton_version(name: &str) -> Vec<u32> {
+	name.split(|x: char| !x.is_ascii_digit())
+		.filter_map(|x| x.parse().ok())
+		.collect()
+}
+
+/// The runtime and prefix Heroic is configured to run the Epic copy of the game with.
+fn heroic_runtime() -> Option<WineRuntime> {
+	let mut config_folders = vec![];
+
+	if let Some(x) = dirs::config_dir() {
+		config_folders.push(x.join("heroic"));
+	}
+
+	if let Some(x) = dirs::home_dir() {
+		config_folders.push(
+			x.join(".var")
+				.join("app")
+				.join("com.heroicgameslauncher.hgl")
+				.join("config")
+				.join("heroic")
+		);
+	}
+
+	config_folders.into_iter().find_map(|folder| {
+		let config = serde_json::from_slice::<Value>(
+			&fs::read(
+				folder
+					.join("GamesConfig")
+					.join(format!("{EPIC_APP_NAME}.json"))
+			)
+			.ok()?
+		)
+		.ok()?;
+
+		let config = config.get(EPIC_APP_NAME)?;
+
+		let prefix = PathBuf::from(config.get("winePrefix")?.as_str()?);
+		let wine_version = config.get("wineVersion")?;
+		let bin = PathBuf::from(wine_version.get("bin")?.as_str()?);
+
+		Some(match wine_version.get("type").and_then(|x| x.as_str()) {
+			Some("proton") => WineRuntime::Proton {
+				proton: bin,
+				compat_data: prefix,
+				steam_root: steam_roots().unwrap_or_default().into_iter().next()
+			},
+
+			_ => WineRuntime::Wine {
+				wine: bin,
+				prefix: Some(prefix)
+			}
+		})
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_util::temp_folder;
+
+	#[test]
+	fn compares_proton_versions_as_numbers() {
+		assert!(proton_version("Proton 10.0") > proton_version("Proton 9.0 (Beta)"));
+		assert!(proton_version("GE-Proton9-20") > proton_version("GE-Proton9-3"));
+		assert!(proton_version("Proton 8.0") > proton_version("Proton - Experimental"));
+	}
+
+	#[test]
+	fn finds_newest_proton() {
+		let steam_root = temp_folder("wine-newest-proton");
+
+		for folder in [
+			"steamapps/common/Proton 9.0 (Beta)",
+			"steamapps/common/Proton 10.0",
+			"steamapps/common/Proton - Experimental",
+			"compatibilitytools.d/GE-Proton9-20"
+		] {
+			fs::create_dir_all(steam_root.join(folder)).unwrap();
+			fs::write(steam_root.join(folder).join("proton"), b"").unwrap();
+		}
+
+		assert_eq!(
+			newest_proton(&[steam_root.to_owned()]),
+			Some(steam_root.join("steamapps/common/Proton 10.0/proton"))
+		);
+
+		fs::remove_dir_all(steam_root).unwrap();
+	}
+}
@@ -0,0 +1,617 @@
+use std::{path::PathBuf, sync::Arc};
+
+use poll_promise::Promise;
+
+use crate::{
+	api::{Download, InstallPlan},
+	error::InstallerError,
+	processes::RunningProcess,
+	uninstall::UninstallReport
+};
+
+/// Work running on another thread, started on entering the state it belongs to.
+pub type Work<T> = Option<Promise<Result<T, InstallerError>>>;
+
+/// Where the installer is in installing the framework. Each state holds what it's working on, so
+/// that nothing is left over from a state that's been left.
+///
+/// Game folders are indexes into the installable folders found so far.
+pub enum WizardState {
+	/// Looking for game folders.
+	Detecting {
+		/// The error the user went back from, if any, for diagnostics.
+		last_error: Option<InstallerError>
+	},
+
+	/// Picking which game folder to install to, or one to uninstall from.
+	Choosing {
+		selected: Option<usize>,
+		last_error: Option<InstallerError>
+	},
+
+	/// Reviewing warnings about the chosen folder and picking install options.
+	Options {
+		folder: usize
+	},
+
+	/// Downloading the framework and checking the download is a framework release.
+	Downloading {
+		folder: usize,
+		download: Work<Arc<Download>>
+	},
+
+	/// Waiting for the game folder to be ready to write to, then checking what installing will run
+	/// into.
+	Verifying {
+		folder: usize,
+		download: Arc<Download>,
+		plan: Work<InstallPlan>
+	},
+
+	Installing {
+		folder: usize,
+		download: Arc<Download>,
+		install: Work<()>
+	},
+
+	Done {
+		folder: usize
+	},
+
+	/// Closing what's running from a folder the framework is already installed in, then
+	/// uninstalling it.
+	Uninstalling {
+		folder: PathBuf,
+		close: Vec<RunningProcess>,
+		uninstall: Work<UninstallReport>
+	},
+
+	Uninstalled {
+		folder: PathBuf,
+		report: UninstallReport
+	},
+
+	/// Something went wrong; retrying goes back to the state it went wrong in, which starts its
+	/// work again.
+	Failed {
+		previous: Box<WizardState>,
+		error: InstallerError
+	}
+}
+
+#[derive(Clone, Debug)]
+pub enum WizardEvent {
+	/// Detection found a game folder or finished looking.
+	Detected,
+
+	Next,
+	Back,
+	Downloaded(Arc<Download>),
+	Verified,
+	Installed,
+
+	/// Uninstall from a folder the framework is already installed in, closing the processes first.
+	Uninstall {
+		folder: PathBuf,
+		close: Vec<RunningProcess>
+	},
+
+	Uninstalled(UninstallReport),
+	Failed(InstallerError),
+	Retry,
+
+	/// Stop downloading or installing and go back to the options, or stop uninstalling and look
+	/// at the folders again.
+	Cancel,
+
+	/// Start looking for game folders again.
+	Rescan
+}
+
+impl Default for WizardState {
+	fn default() -> Self {
+		WizardState::Detecting { last_error: None }
+	}
+}
+
+impl WizardState {
+	/// The state after an event. Events that don't apply to the current state are ignored, so that
+	/// nothing can, for example, install a second time once installing is done.
+	pub fn next(self, event: WizardEvent) -> Self {
+		match (self, event) {
+			(WizardState::Detecting { last_error }, WizardEvent::Detected) => {
+				WizardState::Choosing {
+					selected: None,
+					last_error
+				}
+			}
+
+			(
+				WizardState::Detecting { last_error } | WizardState::Choosing { last_error, .. },
+				WizardEvent::Rescan
+			) => WizardState::Detecting { last_error },
+
+			(
+				WizardState::Choosing {
+					selected: Some(folder),
+					..
+				},
+				WizardEvent::Next
+			) => WizardState::Options { folder },
+
+			// Folders the framework is already installed in are listed as they're found
+			(
+				WizardState::Detecting { .. } | WizardState::Choosing { .. },
+				WizardEvent::Uninstall { folder, close }
+			) => WizardState::Uninstalling {
+				folder,
+				close,
+				uninstall: None
+			},
+
+			(WizardState::Uninstalling { folder, .. }, WizardEvent::Uninstalled(report)) => {
+				WizardState::Uninstalled { folder, report }
+			}
+
+			// Whatever was removed before cancelling stays removed, so the folders are looked at
+			// again
+			(WizardState::Uninstalling { .. }, WizardEvent::Cancel)
+			| (WizardState::Uninstalled { .. }, WizardEvent::Rescan) => WizardState::default(),
+
+			(WizardState::Options { folder }, WizardEvent::Back) => WizardState::Choosing {
+				selected: Some(folder),
+				last_error: None
+			},
+
+			(WizardState::Options { folder }, WizardEvent::Next) => WizardState::Downloading {
+				folder,
+				download: None
+			},
+
+			(WizardState::Downloading { folder, .. }, WizardEvent::Downloaded(download)) => {
+				WizardState::Verifying {
+					folder,
+					download,
+					plan: None
+				}
+			}
+
+			(
+				WizardState::Verifying {
+					folder, download, ..
+				},
+				WizardEvent::Verified
+			) => WizardState::Installing {
+				folder,
+				download,
+				install: None
+			},
+
+			(WizardState::Installing { folder, .. }, WizardEvent::Installed) => {
+				WizardState::Done { folder }
+			}
+
+			(
+				WizardState::Downloading { folder, .. }
+				| WizardState::Verifying { folder, .. }
+				| WizardState::Installing { folder, .. },
+				WizardEvent::Cancel
+			) => WizardState::Options { folder },
+
+			(WizardState::Failed { previous, .. }, WizardEvent::Retry) => *previous,
+
+			(WizardState::Failed { error, .. }, WizardEvent::Back) => WizardState::Detecting {
+				last_error: Some(error)
+			},
+
+			(state @ WizardState::Failed { .. }, WizardEvent::Failed(_)) => state,
+
+			(state, WizardEvent::Failed(error)) => WizardState::Failed {
+				previous: Box::new(state.restarted()),
+				error
+			},
+
+			(state, _) => state
+		}
+	}
+
+	/// The state as it was on entering it, before its work was started.
+	fn restarted(self) -> Self {
+		match self {
+			WizardState::Downloading { folder, .. } => WizardState::Downloading {
+				folder,
+				download: None
+			},
+
+			WizardState::Verifying {
+				folder, download, ..
+			} => WizardState::Verifying {
+				folder,
+				download,
+				plan: None
+			},
+
+			WizardState::Installing {
+				folder, download, ..
+			} => WizardState::Installing {
+				folder,
+				download,
+				install: None
+			},
+
+			// The user agreed to closing the processes, so retrying closes them again if need be
+			WizardState::Uninstalling { folder, close, .. } => WizardState::Uninstalling {
+				folder,
+				close,
+				uninstall: None
+			},
+
+			state => state
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			WizardState::Detecting { .. } => "detecting",
+			WizardState::Choosing { .. } => "choosing",
+			WizardState::Options { .. } => "options",
+			WizardState::Downloading { .. } => "downloading",
+			WizardState::Verifying { .. } => "verifying",
+			WizardState::Installing { .. } => "installing",
+			WizardState::Done { .. } => "done",
+			WizardState::Uninstalling { .. } => "uninstalling",
+			WizardState::Uninstalled { .. } => "uninstalled",
+			WizardState::Failed { .. } => "failed"
+		}
+	}
+
+	pub fn error(&self) -> Option<&InstallerError> {
+		match self {
+			WizardState::Failed { error, .. } => Some(error),
+			_ => None
+		}
+	}
+
+	/// The current error, or the one the user went back from.
+	pub fn last_error(&self) -> Option<&InstallerError> {
+		match self {
+			WizardState::Failed { error, .. } => Some(error),
+			WizardState::Detecting { last_error } | WizardState::Choosing { last_error, .. } => {
+				last_error.as_ref()
+			}
+			_ => None
+		}
+	}
+
+	/// The game folder being installed to, or the one selected to install to.
+	pub fn folder(&self) -> Option<usize> {
+		match self {
+			WizardState::Choosing { selected, .. } => *selected,
+
+			WizardState::Options { folder }
+			| WizardState::Downloading { folder, .. }
+			| WizardState::Verifying { folder, .. }
+			| WizardState::Installing { folder, .. }
+			| WizardState::Done { folder } => Some(*folder),
+
+			WizardState::Failed { previous, .. } => previous.folder(),
+
+			_ => None
+		}
+	}
+
+	/// Whether the game folder can still be changed.
+	pub fn is_choosing(&self) -> bool {
+		matches!(
+			self,
+			WizardState::Detecting { .. } | WizardState::Choosing { .. }
+		)
+	}
+
+	pub fn is_uninstalling(&self) -> bool {
+		matches!(
+			self,
+			WizardState::Uninstalling { .. } | WizardState::Uninstalled { .. }
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn error(detail: &str) -> InstallerError {
+		InstallerError::Verification {
+			path: PathBuf::from("HITMAN 3"),
+			detail: detail.to_owned()
+		}
+	}
+
+	fn download() -> Arc<Download> {
+		Arc::new(Download {
+			data: vec![],
+			version: None
+		})
+	}
+
+	fn failed(previous: WizardState) -> WizardState {
+		previous.next(WizardEvent::Failed(error("failed")))
+	}
+
+	/// The state after each event in turn.
+	fn run(state: WizardState, events: Vec<WizardEvent>) -> WizardState {
+		events.into_iter().fold(state, WizardState::next)
+	}
+
+	fn choosing(selected: Option<usize>) -> WizardState {
+		WizardState::Choosing {
+			selected,
+			last_error: None
+		}
+	}
+
+	fn uninstalling() -> WizardState {
+		choosing(None).next(WizardEvent::Uninstall {
+			folder: PathBuf::from("Old"),
+			close: vec![]
+		})
+	}
+
+	#[test]
+	fn installs() {
+		let state = run(
+			choosing(Some(1)),
+			vec![
+				WizardEvent::Next,
+				WizardEvent::Next,
+				WizardEvent::Downloaded(download()),
+				WizardEvent::Verified,
+				WizardEvent::Installed,
+			]
+		);
+
+		assert_eq!(state.name(), "done");
+		assert_eq!(state.folder(), Some(1));
+	}
+
+	#[test]
+	fn detects_then_chooses() {
+		assert_eq!(
+			WizardState::default().next(WizardEvent::Detected).name(),
+			"choosing"
+		);
+
+		// Nothing to go on to without a folder
+		assert_eq!(choosing(None).next(WizardEvent::Next).name(), "choosing");
+	}
+
+	#[test]
+	fn goes_back_and_rescans() {
+		let state = choosing(Some(2)).next(WizardEvent::Next);
+		let state = state.next(WizardEvent::Back);
+
+		assert_eq!(state.name(), "choosing");
+		assert_eq!(state.folder(), Some(2));
+
+		assert_eq!(
+			choosing(Some(0)).next(WizardEvent::Rescan).name(),
+			"detecting"
+		);
+		assert_eq!(
+			WizardState::default().next(WizardEvent::Rescan).name(),
+			"detecting"
+		);
+
+		// The folder can't be changed from under a download
+		let downloading = || {
+			run(
+				choosing(Some(0)),
+				vec![WizardEvent::Next, WizardEvent::Next]
+			)
+		};
+
+		assert_eq!(
+			downloading().next(WizardEvent::Rescan).name(),
+			"downloading"
+		);
+		assert_eq!(downloading().next(WizardEvent::Back).name(), "downloading");
+	}
+
+	#[test]
+	fn retry_starts_work_again() {
+		let state = failed(WizardState::Downloading {
+			folder: 0,
+			download: Some(Promise::from_ready(Err(error("download"))))
+		})
+		.next(WizardEvent::Retry);
+
+		assert!(matches!(
+			state,
+			WizardState::Downloading {
+				folder: 0,
+				download: None
+			}
+		));
+
+		let state = failed(WizardState::Installing {
+			folder: 0,
+			download: download(),
+			install: Some(Promise::from_ready(Err(error("install"))))
+		})
+		.next(WizardEvent::Retry);
+
+		assert!(matches!(
+			state,
+			WizardState::Installing { install: None, .. }
+		));
+
+		let state = failed(WizardState::Uninstalling {
+			folder: PathBuf::from("Old"),
+			close: vec![],
+			uninstall: Some(Promise::from_ready(Err(error("uninstall"))))
+		})
+		.next(WizardEvent::Retry);
+
+		assert!(matches!(
+			state,
+			WizardState::Uninstalling {
+				uninstall: None,
+				..
+			}
+		));
+	}
+
+	#[test]
+	fn retry_after_uninstall_and_rescan_only_installs() {
+		let state = run(
+			uninstalling(),
+			vec![
+				WizardEvent::Uninstalled(UninstallReport::default()),
+				WizardEvent::Rescan,
+				WizardEvent::Detected,
+			]
+		);
+
+		let state = match state {
+			WizardState::Choosing { last_error, .. } => WizardState::Choosing {
+				selected: Some(0),
+				last_error
+			},
+			state => panic!("Expected choosing, got {}", state.name())
+		};
+
+		let state = run(
+			state,
+			vec![
+				WizardEvent::Next,
+				WizardEvent::Next,
+				WizardEvent::Downloaded(download()),
+				WizardEvent::Verified,
+				WizardEvent::Failed(error("install")),
+				WizardEvent::Retry,
+			]
+		);
+
+		assert!(matches!(
+			state,
+			WizardState::Installing {
+				folder: 0,
+				install: None,
+				..
+			}
+		));
+	}
+
+	#[test]
+	fn uninstalls_without_rescanning_midway() {
+		assert_eq!(uninstalling().name(), "uninstalling");
+		assert_eq!(
+			uninstalling().next(WizardEvent::Rescan).name(),
+			"uninstalling"
+		);
+		assert_eq!(
+			uninstalling().next(WizardEvent::Next).name(),
+			"uninstalling"
+		);
+		assert_eq!(uninstalling().next(WizardEvent::Cancel).name(), "detecting");
+
+		let uninstalled = uninstalling().next(WizardEvent::Uninstalled(UninstallReport::default()));
+
+		assert_eq!(uninstalled.name(), "uninstalled");
+		assert_eq!(uninstalled.next(WizardEvent::Rescan).name(), "detecting");
+
+		// Only while the folder can still be changed
+		assert_eq!(
+			WizardState::Options { folder: 0 }
+				.next(WizardEvent::Uninstall {
+					folder: PathBuf::from("Old"),
+					close: vec![]
+				})
+				.name(),
+			"options"
+		);
+	}
+
+	#[test]
+	fn back_from_failed_rescans_keeping_error() {
+		let state = failed(WizardState::Options { folder: 0 }).next(WizardEvent::Back);
+
+		assert_eq!(state.name(), "detecting");
+		assert!(state.error().is_none());
+		assert!(state.last_error().is_some());
+	}
+
+	#[test]
+	fn installed_ignored_outside_installing() {
+		for state in [
+			WizardState::Options { folder: 0 },
+			WizardState::Downloading {
+				folder: 0,
+				download: None
+			},
+			WizardState::Done { folder: 0 }
+		] {
+			let name = state.name();
+
+			assert_eq!(state.next(WizardEvent::Installed).name(), name);
+		}
+
+		assert_eq!(
+			failed(WizardState::Options { folder: 0 })
+				.next(WizardEvent::Installed)
+				.name(),
+			"failed"
+		);
+	}
+
+	#[test]
+	fn failed_on_failed_keeps_original() {
+		let state =
+			failed(WizardState::Options { folder: 0 }).next(WizardEvent::Failed(error("second")));
+
+		match &state {
+			WizardState::Failed {
+				previous,
+				error: InstallerError::Verification { detail, .. }
+			} => {
+				assert_eq!(detail, "failed");
+				assert_eq!(previous.name(), "options");
+			}
+
+			x => panic!("Expected the original failure, got {}", x.name())
+		}
+	}
+
+	#[test]
+	fn cancel_returns_to_options() {
+		for state in [
+			WizardState::Downloading {
+				folder: 3,
+				download: None
+			},
+			WizardState::Verifying {
+				folder: 3,
+				download: download(),
+				plan: None
+			},
+			WizardState::Installing {
+				folder: 3,
+				download: download(),
+				install: None
+			}
+		] {
+			let state = state.next(WizardEvent::Cancel);
+
+			assert_eq!(state.name(), "options");
+			assert_eq!(state.folder(), Some(3));
+		}
+
+		assert_eq!(choosing(None).next(WizardEvent::Cancel).name(), "choosing");
+		assert_eq!(
+			WizardState::Done { folder: 0 }
+				.next(WizardEvent::Cancel)
+				.name(),
+			"done"
+		);
+	}
+}